@@ -0,0 +1,12 @@
+create table if not exists slack_events (
+  id integer primary key not null,
+  event_id text not null,
+  slack_team_id text not null,
+  event_time integer not null,
+  started_at integer not null,
+  finished_at integer,
+  created_at text not null default (datetime('now', 'utc'))
+);
+
+create unique index index_slack_events_on_event_id on slack_events(event_id);
+create index index_slack_events_on_event_time on slack_events(event_time);
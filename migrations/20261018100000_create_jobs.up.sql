@@ -0,0 +1,14 @@
+create table if not exists jobs (
+  id integer primary key not null,
+  slack_team_id text not null,
+  payload text not null,
+  status text not null default 'pending',
+  attempts integer not null default 0,
+  run_at integer not null,
+  locked_at integer,
+  last_error text,
+  created_at text not null default (datetime('now', 'utc'))
+);
+
+create index index_jobs_on_status_and_run_at on jobs(status, run_at);
+create index index_jobs_on_slack_team_id on jobs(slack_team_id);
@@ -0,0 +1 @@
+alter table teams drop column slack_bot_token;
@@ -0,0 +1 @@
+alter table teams add column slack_bot_token text;
@@ -0,0 +1 @@
+alter table reactions add column include_thread boolean not null default true;
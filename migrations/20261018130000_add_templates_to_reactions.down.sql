@@ -0,0 +1,2 @@
+alter table reactions drop column title_template;
+alter table reactions drop column body_template;
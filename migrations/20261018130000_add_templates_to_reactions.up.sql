@@ -0,0 +1,2 @@
+alter table reactions add column title_template text;
+alter table reactions add column body_template text;
@@ -0,0 +1,3 @@
+drop table if exists reaction_labels;
+alter table reactions drop column milestone;
+alter table reactions drop column issue_type;
@@ -0,0 +1,12 @@
+create table if not exists reaction_labels (
+  id integer primary key not null,
+  reaction_id integer not null,
+  name text not null,
+  created_at text not null default (datetime('now', 'utc')),
+  foreign key (reaction_id) references reactions(id) on delete cascade
+);
+
+create unique index index_reaction_id_and_name_on_reaction_labels on reaction_labels(reaction_id, name);
+
+alter table reactions add column milestone integer;
+alter table reactions add column issue_type text;
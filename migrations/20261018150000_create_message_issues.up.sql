@@ -0,0 +1,13 @@
+create table if not exists message_issues (
+  id integer primary key not null,
+  team_id integer not null,
+  channel text not null,
+  ts text not null,
+  repo text not null,
+  issue_number integer not null,
+  html_url text not null,
+  created_at text not null default (datetime('now', 'utc')),
+  foreign key (team_id) references teams(id) on delete cascade
+);
+
+create unique index index_team_id_and_channel_and_ts_on_message_issues on message_issues(team_id, channel, ts);
@@ -0,0 +1 @@
+alter table teams drop column closed_issue_reaction;
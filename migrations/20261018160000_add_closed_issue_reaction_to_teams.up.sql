@@ -0,0 +1 @@
+alter table teams add column closed_issue_reaction text;
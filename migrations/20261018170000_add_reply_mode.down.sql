@@ -0,0 +1,2 @@
+alter table teams drop column reply_mode;
+alter table reactions drop column reply_mode;
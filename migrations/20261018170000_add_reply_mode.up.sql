@@ -0,0 +1,2 @@
+alter table teams add column reply_mode text not null default 'thread';
+alter table reactions add column reply_mode text;
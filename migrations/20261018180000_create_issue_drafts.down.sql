@@ -0,0 +1,2 @@
+drop table if exists issue_drafts;
+alter table reactions drop column confirm;
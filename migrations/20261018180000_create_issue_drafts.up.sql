@@ -0,0 +1,20 @@
+alter table reactions add column confirm boolean not null default false;
+
+create table if not exists issue_drafts (
+  id integer primary key not null,
+  team_id integer not null,
+  user text not null,
+  channel text not null,
+  ts text not null,
+  thread_ts text not null,
+  reply_mode text not null,
+  repo text not null,
+  title text not null,
+  body text not null,
+  labels text not null,
+  assignees text not null,
+  milestone integer,
+  issue_type text,
+  created_at text not null default (datetime('now', 'utc')),
+  foreign key (team_id) references teams(id) on delete cascade
+);
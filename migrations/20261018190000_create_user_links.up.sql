@@ -0,0 +1,10 @@
+create table if not exists user_links (
+  id integer primary key not null,
+  team_id integer not null,
+  slack_user_id text not null,
+  github_login text not null,
+  created_at text not null default (datetime('now', 'utc')),
+  foreign key (team_id) references teams(id) on delete cascade
+);
+
+create unique index index_team_id_and_slack_user_id_on_user_links on user_links(team_id, slack_user_id);
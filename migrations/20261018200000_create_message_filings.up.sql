@@ -0,0 +1,11 @@
+create table if not exists message_filings (
+  id integer primary key not null,
+  team_id integer not null,
+  channel text not null,
+  ts text not null,
+  started_at integer not null,
+  created_at text not null default (datetime('now', 'utc')),
+  foreign key (team_id) references teams(id) on delete cascade
+);
+
+create unique index index_team_id_and_channel_and_ts_on_message_filings on message_filings(team_id, channel, ts);
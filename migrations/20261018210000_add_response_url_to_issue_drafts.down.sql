@@ -0,0 +1 @@
+alter table issue_drafts drop column response_url;
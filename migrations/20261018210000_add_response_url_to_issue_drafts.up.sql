@@ -0,0 +1 @@
+alter table issue_drafts add column response_url text;
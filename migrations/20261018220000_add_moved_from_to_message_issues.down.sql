@@ -0,0 +1,2 @@
+alter table message_issues drop column moved_from_issue_number;
+alter table message_issues drop column moved_from_repo;
@@ -0,0 +1,2 @@
+alter table message_issues add column moved_from_repo text;
+alter table message_issues add column moved_from_issue_number integer;
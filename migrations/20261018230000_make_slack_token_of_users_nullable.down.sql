@@ -0,0 +1,15 @@
+create table users_old (
+  id integer primary key not null,
+  slack_team_id text not null,
+  slack_user_id text not null,
+  slack_token text not null,
+  created_at text not null default (datetime('now', 'utc'))
+);
+
+insert into users_old (id, slack_team_id, slack_user_id, slack_token, created_at)
+  select id, slack_team_id, slack_user_id, coalesce(slack_token, ''), created_at from users;
+
+drop table users;
+alter table users_old rename to users;
+
+create unique index if not exists index_users_on_slack_user_id on users(slack_user_id);
@@ -0,0 +1,17 @@
+-- Nothing reads user tokens, and the stored ones are either empty or unencrypted, so they are
+-- dropped rather than carried over.
+create table users_new (
+  id integer primary key not null,
+  slack_team_id text not null,
+  slack_user_id text not null,
+  slack_token text,
+  created_at text not null default (datetime('now', 'utc'))
+);
+
+insert into users_new (id, slack_team_id, slack_user_id, slack_token, created_at)
+  select id, slack_team_id, slack_user_id, null, created_at from users;
+
+drop table users;
+alter table users_new rename to users;
+
+create unique index if not exists index_users_on_slack_user_id on users(slack_user_id);
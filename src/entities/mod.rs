@@ -2,7 +2,14 @@
 
 pub mod prelude;
 
+pub mod issue_draft;
+pub mod job;
+pub mod message_filing;
+pub mod message_issue;
 pub mod reaction;
 pub mod reaction_assignee;
+pub mod reaction_label;
+pub mod slack_event;
 pub mod team;
 pub mod user;
+pub mod user_link;
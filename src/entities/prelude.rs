@@ -1,6 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.5.0
 
 pub use super::{
-    reaction::Entity as Reaction, reaction_assignee::Entity as ReactionAssignee,
-    team::Entity as Team, user::Entity as User,
+    issue_draft::Entity as IssueDraft, job::Entity as Job, message_filing::Entity as MessageFiling,
+    message_issue::Entity as MessageIssue, reaction::Entity as Reaction,
+    reaction_assignee::Entity as ReactionAssignee, reaction_label::Entity as ReactionLabel,
+    slack_event::Entity as SlackEvent, team::Entity as Team, user::Entity as User,
+    user_link::Entity as UserLink,
 };
@@ -12,6 +12,13 @@ pub struct Model {
     pub team_id: i32,
     pub repo: String,
     pub created_at: String,
+    pub include_thread: bool,
+    pub title_template: Option<String>,
+    pub body_template: Option<String>,
+    pub milestone: Option<i32>,
+    pub issue_type: Option<String>,
+    pub reply_mode: Option<String>,
+    pub confirm: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -26,6 +33,8 @@ pub enum Relation {
     Teams,
     #[sea_orm(has_many = "super::reaction_assignee::Entity")]
     ReactionAssignees,
+    #[sea_orm(has_many = "super::reaction_label::Entity")]
+    ReactionLabels,
 }
 
 impl Related<super::team::Entity> for Entity {
@@ -40,4 +49,19 @@ impl Related<super::reaction_assignee::Entity> for Entity {
     }
 }
 
+impl Related<super::reaction_label::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ReactionLabels.def()
+    }
+}
+
+impl Entity {
+    pub fn find_by_slack_team_id_and_name(slack_team_id: &str, name: &str) -> Select<Entity> {
+        Self::find()
+            .inner_join(super::team::Entity)
+            .filter(super::team::Column::SlackTeamId.eq(slack_team_id))
+            .filter(Column::Name.eq(name))
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
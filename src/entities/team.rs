@@ -11,12 +11,21 @@ pub struct Model {
     pub slack_team_id: String,
     pub created_at: String,
     pub github_installation_id: Option<i32>,
+    pub slack_bot_token: Option<String>,
+    pub closed_issue_reaction: Option<String>,
+    pub reply_mode: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::reaction::Entity")]
     Reactions,
+    #[sea_orm(has_many = "super::message_issue::Entity")]
+    MessageIssues,
+    #[sea_orm(has_many = "super::issue_draft::Entity")]
+    IssueDrafts,
+    #[sea_orm(has_many = "super::user_link::Entity")]
+    UserLinks,
 }
 
 impl Related<super::reaction::Entity> for Entity {
@@ -25,4 +34,22 @@ impl Related<super::reaction::Entity> for Entity {
     }
 }
 
+impl Related<super::message_issue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageIssues.def()
+    }
+}
+
+impl Related<super::issue_draft::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IssueDrafts.def()
+    }
+}
+
+impl Related<super::user_link::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserLinks.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -10,7 +10,9 @@ pub struct Model {
     pub id: i32,
     pub slack_team_id: String,
     pub slack_user_id: String,
-    pub slack_token: String,
+    /// Encrypted user token, given only when the install asks for user scopes
+    #[serde(skip_serializing)]
+    pub slack_token: Option<String>,
     pub created_at: String,
 }
 
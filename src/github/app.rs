@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    sync::{Mutex, OnceLock},
+};
+
+use jwt::{PKeyWithDigest, SignWithKey};
+use openssl::{hash::MessageDigest, pkey::PKey};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::clock::unix_now;
+
+use super::{
+    rest_api::{send, GithubRestApi},
+    GithubClientError,
+};
+
+// Installation tokens are valid for an hour. Mint a new one a bit before that,
+// so a token doesn't expire in the middle of a job.
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+struct InstallationToken {
+    token: String,
+    expires_at: i64,
+}
+
+static INSTALLATION_TOKENS: OnceLock<Mutex<HashMap<i32, InstallationToken>>> = OnceLock::new();
+
+fn installation_tokens() -> &'static Mutex<HashMap<i32, InstallationToken>> {
+    INSTALLATION_TOKENS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Generates a JWT authenticating as the GitHub App itself.
+/// https://docs.github.com/en/developers/apps/building-github-apps/authenticating-with-github-apps#authenticating-as-a-github-app
+pub fn generate_jwt() -> Result<String, Box<dyn std::error::Error>> {
+    let github_app_id = env::var("GITHUB_APP_ID")?;
+    let private_key_path = env::var("GITHUB_APP_PRIVATE_KEY_PATH")?;
+    let private_key = PKey::private_key_from_pem(&fs::read(private_key_path)?)?;
+
+    let now = unix_now();
+    let body = json!({
+        "iat": now - 60,
+        "exp": now + (10 * 60),
+        "iss": github_app_id
+    });
+    let key = PKeyWithDigest {
+        digest: MessageDigest::sha256(),
+        key: private_key,
+    };
+
+    Ok(body.sign_with_key(&key)?)
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    token: String,
+    expires_at: String,
+}
+
+/// Returns an access token for the installation, minting a new one when the cached one is
+/// about to expire.
+pub(super) async fn installation_token(
+    api: &GithubRestApi,
+    installation_id: i32,
+) -> Result<String, GithubClientError> {
+    if let Some(token) = installation_tokens()
+        .lock()
+        .unwrap()
+        .get(&installation_id)
+        .filter(|token| is_fresh(token.expires_at, unix_now()))
+    {
+        return Ok(token.token.clone());
+    }
+
+    let jwt = generate_jwt().map_err(|e| GithubClientError::AppAuth(e.to_string()))?;
+    let request = api.post(&format!(
+        "/app/installations/{}/access_tokens",
+        installation_id
+    ));
+    let data: AccessTokenResponse = send(request, &jwt).await?;
+    let expires_at = chrono::DateTime::parse_from_rfc3339(&data.expires_at)
+        .map_err(|e| GithubClientError::Json {
+            status: 201,
+            message: e.to_string(),
+            body: data.expires_at.clone(),
+        })?
+        .timestamp();
+
+    installation_tokens().lock().unwrap().insert(
+        installation_id,
+        InstallationToken {
+            token: data.token.clone(),
+            expires_at,
+        },
+    );
+
+    Ok(data.token)
+}
+
+fn is_fresh(expires_at: i64, now: i64) -> bool {
+    now < expires_at - TOKEN_REFRESH_MARGIN_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_fresh;
+
+    #[test]
+    fn test_is_fresh() {
+        let expires_at = 1_660_000_000;
+        assert!(is_fresh(expires_at, expires_at - 60 * 60));
+        assert!(!is_fresh(expires_at, expires_at - 60));
+        assert!(!is_fresh(expires_at, expires_at + 60));
+    }
+}
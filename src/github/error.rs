@@ -0,0 +1,240 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Error of a GitHub API call.
+#[derive(Debug)]
+pub enum GithubClientError {
+    /// No response came back, e.g. the connection failed or timed out. `retryable` is false when
+    /// a call that must not be made twice may have reached GitHub.
+    Request {
+        message: String,
+        timeout: bool,
+        retryable: bool,
+    },
+    /// GitHub answered with an error status. `message` is the `message` field of the error body.
+    /// https://docs.github.com/en/rest/overview/resources-in-the-rest-api#client-errors
+    Api {
+        status: u16,
+        message: String,
+        body: String,
+    },
+    /// The response isn't what the endpoint returns
+    Json {
+        status: u16,
+        body: String,
+        message: String,
+    },
+    /// The GitHub App couldn't sign a JWT, e.g. its private key is missing
+    AppAuth(String),
+}
+
+impl GithubClientError {
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            GithubClientError::Api { status, .. } | GithubClientError::Json { status, .. } => {
+                Some(*status)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            GithubClientError::Api { body, .. } | GithubClientError::Json { body, .. } => {
+                Some(body)
+            }
+            _ => None,
+        }
+    }
+
+    // Primary rate limits answer 403, secondary ones 403 or 429, with a message saying so.
+    fn is_rate_limited(&self) -> bool {
+        match self {
+            GithubClientError::Api { status: 429, .. } => true,
+            GithubClientError::Api {
+                status: 403,
+                message,
+                ..
+            } => message.to_lowercase().contains("rate limit"),
+            _ => false,
+        }
+    }
+
+    /// Whether the same call may succeed later. Other errors need someone to fix something.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GithubClientError::Request { retryable, .. } => *retryable,
+            GithubClientError::Api { status, .. } => self.is_rate_limited() || *status >= 500,
+            GithubClientError::Json { .. } | GithubClientError::AppAuth(_) => false,
+        }
+    }
+
+    /// What went wrong and what to do about it, for the user who triggered the call.
+    pub fn user_message(&self) -> String {
+        if self.is_rate_limited() {
+            return "GitHub is rate limiting emoji-to-do. Please try again in a few minutes."
+                .to_string();
+        }
+
+        match self {
+            GithubClientError::Request { .. } => {
+                "emoji-to-do couldn't reach GitHub. Please try again later.".to_string()
+            }
+            GithubClientError::Api {
+                status, message, ..
+            } => match status {
+                401 => "emoji-to-do is no longer authorized on GitHub. Ask an admin to reinstall the GitHub App."
+                    .to_string(),
+                403 => format!(
+                    "The GitHub App doesn't have permission to do this: {}",
+                    message
+                ),
+                404 => "The repository or issue was not found. Check that the GitHub App is installed on the repository."
+                    .to_string(),
+                410 => "The issue was deleted, or issues are disabled on the repository."
+                    .to_string(),
+                422 => format!("GitHub rejected the request: {}", message),
+                status => format!("GitHub returned an error ({}): {}", status, message),
+            },
+            GithubClientError::Json { .. } => {
+                "GitHub returned an unexpected response. Please try again later.".to_string()
+            }
+            GithubClientError::AppAuth(_) => {
+                "emoji-to-do couldn't authenticate as the GitHub App. Please contact the admin."
+                    .to_string()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GithubClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GithubClientError::Request { message, .. } => {
+                write!(f, "github request failed: {}", message)
+            }
+            GithubClientError::Api {
+                status, message, ..
+            } => write!(f, "github api error: {} ({})", message, status),
+            GithubClientError::Json {
+                status, message, ..
+            } => write!(f, "github json parse error: {} ({})", message, status),
+            GithubClientError::AppAuth(message) => {
+                write!(f, "github app authentication failed: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GithubClientError {}
+
+impl From<reqwest::Error> for GithubClientError {
+    fn from(e: reqwest::Error) -> Self {
+        GithubClientError::Request {
+            message: e.to_string(),
+            timeout: e.is_timeout(),
+            retryable: true,
+        }
+    }
+}
+
+// Not found and validation errors are passed on as they are. Other errors are GitHub's, or ours
+// when the app can't authenticate.
+impl ResponseError for GithubClientError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            _ if self.is_rate_limited() => StatusCode::SERVICE_UNAVAILABLE,
+            GithubClientError::Request { timeout: true, .. } => StatusCode::GATEWAY_TIMEOUT,
+            GithubClientError::Api { status: 404, .. } => StatusCode::NOT_FOUND,
+            GithubClientError::Api { status: 410, .. } => StatusCode::GONE,
+            GithubClientError::Api { status: 422, .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            GithubClientError::AppAuth(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).body(self.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+pub(super) fn parse_response<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: String,
+) -> Result<T, GithubClientError> {
+    let status = status.as_u16();
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_str::<ErrorResponse>(&body)
+            .map(|error| error.message)
+            .unwrap_or_default();
+        return Err(GithubClientError::Api {
+            status,
+            message,
+            body,
+        });
+    }
+
+    serde_json::from_str(&body).map_err(|e| GithubClientError::Json {
+        status,
+        message: e.to_string(),
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, ResponseError};
+
+    use super::{parse_response, GithubClientError};
+
+    fn parse(status: u16, body: &str) -> Result<serde_json::Value, GithubClientError> {
+        parse_response(
+            reqwest::StatusCode::from_u16(status).unwrap(),
+            body.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse(201, r#"{"number":1}"#).unwrap()["number"], 1);
+
+        let e = parse(404, r#"{"message":"Not Found"}"#).unwrap_err();
+        assert!(
+            matches!(&e, GithubClientError::Api { status: 404, message, .. } if message == "Not Found")
+        );
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+        assert!(!e.is_retryable());
+
+        let e = parse(422, r#"{"message":"Validation Failed","errors":[]}"#).unwrap_err();
+        assert_eq!(e.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(e.user_message().contains("Validation Failed"));
+
+        let e = parse(
+            403,
+            r#"{"message":"API rate limit exceeded for installation ID 1."}"#,
+        )
+        .unwrap_err();
+        assert!(e.is_retryable());
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let e = parse(
+            403,
+            r#"{"message":"Resource not accessible by integration"}"#,
+        )
+        .unwrap_err();
+        assert!(!e.is_retryable());
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+
+        let e = parse(502, "<html>Bad Gateway</html>").unwrap_err();
+        assert!(matches!(&e, GithubClientError::Api { message, .. } if message.is_empty()));
+        assert!(e.is_retryable());
+
+        let e = parse(200, "not json").unwrap_err();
+        assert_eq!(e.status(), Some(200));
+    }
+}
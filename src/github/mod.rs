@@ -1,70 +1,179 @@
-use std::{collections::HashMap, env};
-
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+pub mod app;
+mod error;
+mod rest_api;
+pub mod webhook;
+
+pub use error::GithubClientError;
+pub use rest_api::{GithubRestApi, GITHUB_API_URL};
+
 #[derive(Deserialize)]
 pub struct Issue {
+    pub number: i32,
     pub html_url: String,
+    #[serde(default)]
+    pub title: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub assignees: Vec<User>,
 }
 
-#[derive(Debug)]
-pub enum GithubClientError {
-    ApiError,
-    JsonError,
-}
+impl Issue {
+    pub fn is_closed(&self) -> bool {
+        self.state == "closed"
+    }
 
-impl std::fmt::Display for GithubClientError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            GithubClientError::ApiError => write!(f, "github returned api error"),
-            GithubClientError::JsonError => write!(f, "github returned json error"),
-        }
+    pub fn label_names(&self) -> Vec<String> {
+        self.labels.iter().map(|label| label.name.clone()).collect()
     }
-}
-impl std::error::Error for GithubClientError {}
-
-pub async fn create_issue(
-    repo: &str,
-    title: &str,
-    body: &str,
-) -> Result<Issue, Box<dyn std::error::Error>> {
-    let token = env::var("GITHUB_TOKEN").unwrap_or_default();
-
-    let client = reqwest::Client::new();
-
-    let mut params = HashMap::new();
-    params.insert("title", title);
-    params.insert("body", body);
-
-    let resp = client
-        .post(format!("https://api.github.com/repos/{}/issues", repo))
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/vnd.github.v3+json")
-        .header("User-Agent", "uiur/emoji-to-do")
-        .bearer_auth(token)
-        .json(&params)
-        .send()
-        .await
-        .map_err(|_e| GithubClientError::ApiError)?;
-
-    log::debug!("{:#?}", resp);
-    if !resp.status().is_success() {
-        log::error!("{:#?}", resp.text().await?);
-        return Err(GithubClientError::ApiError.into());
+
+    pub fn assignee_logins(&self) -> Vec<String> {
+        self.assignees
+            .iter()
+            .map(|assignee| assignee.login.clone())
+            .collect()
     }
 
-    let issue = resp
-        .json::<Issue>()
-        .await
-        .map_err(|_e| GithubClientError::JsonError)?;
+    /// Returns the logins in `logins` that did not end up assigned to the issue.
+    pub fn unassigned_logins(&self, logins: &[String]) -> Vec<String> {
+        logins
+            .iter()
+            .filter(|login| {
+                !self
+                    .assignees
+                    .iter()
+                    .any(|assignee| assignee.login.eq_ignore_ascii_case(login))
+            })
+            .cloned()
+            .collect()
+    }
+}
 
-    Ok(issue)
-    // Err(GithubClientError::ApiError.into())
+#[derive(Deserialize)]
+pub struct User {
+    pub login: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ListUserInstallationResponse {
-    installations: Vec<Installation>,
+#[derive(Deserialize)]
+pub struct IssueComment {
+    pub html_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+/// Parameters of a new issue.
+/// https://docs.github.com/en/rest/issues/issues#create-an-issue
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NewIssue {
+    pub title: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assignees: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<i32>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub issue_type: Option<String>,
+}
+
+/// The GitHub REST API endpoints the app calls. `GithubRestApi` calls GitHub, and tests point it
+/// at a local fake.
+#[async_trait]
+pub trait IssueTracker: Send + Sync {
+    /// Returns an access token for the installation of the GitHub App.
+    async fn installation_token(&self, installation_id: i32) -> Result<String, GithubClientError>;
+
+    /// Assignees who can't be assigned are dropped. Callers should check
+    /// `Issue::unassigned_logins`.
+    async fn create_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        params: &NewIssue,
+    ) -> Result<Issue, GithubClientError>;
+
+    async fn create_comment(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        body: &str,
+    ) -> Result<IssueComment, GithubClientError>;
+
+    async fn get_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+    ) -> Result<Issue, GithubClientError>;
+
+    async fn update_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        params: &serde_json::Value,
+    ) -> Result<Issue, GithubClientError>;
+
+    /// Logins that can't be assigned are ignored. Check `Issue::unassigned_logins`.
+    async fn add_assignees(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        logins: &[String],
+    ) -> Result<Issue, GithubClientError>;
+
+    async fn list_labels(&self, token: &str, repo: &str) -> Result<Vec<Label>, GithubClientError>;
+
+    /// Installations of the app the user of `token` can access.
+    async fn list_user_installations(
+        &self,
+        token: &str,
+    ) -> Result<Vec<Installation>, GithubClientError>;
+
+    async fn get_authenticated_user(&self, token: &str) -> Result<User, GithubClientError>;
+}
+
+/// Returns the names in `names` that are not labels of the repository. GitHub matches label names
+/// case-insensitively.
+pub fn missing_labels(names: &[String], labels: &[Label]) -> Vec<String> {
+    names
+        .iter()
+        .filter(|name| {
+            !labels
+                .iter()
+                .any(|label| label.name.eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Parses an issue url like `https://github.com/owner/repo/issues/1` into the repo and the issue
+/// number.
+pub fn parse_issue_url(url: &str) -> Option<(String, i32)> {
+    let path = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))?;
+    let path = path.split(['#', '?']).next()?;
+
+    match path.trim_end_matches('/').split('/').collect::<Vec<_>>()[..] {
+        [owner, repo, "issues", number] if !owner.is_empty() && !repo.is_empty() => {
+            let number = number.parse().ok()?;
+            Some((format!("{}/{}", owner, repo), number))
+        }
+        _ => None,
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,21 +181,111 @@ pub struct Installation {
     pub id: i32,
 }
 
-pub async fn list_user_installations(
-    token: &str,
-) -> Result<Vec<Installation>, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let resp = client
-        .get("https://api.github.com/user/installations")
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/vnd.github.v3+json")
-        .header("User-Agent", "uiur/emoji-to-do")
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|_e| GithubClientError::ApiError)?;
-
-    log::debug!("{:#?}", resp);
-    let result = resp.json::<ListUserInstallationResponse>().await?;
-    Ok(result.installations)
+#[cfg(test)]
+mod tests {
+    use super::{missing_labels, parse_issue_url, Issue, Label, NewIssue, User};
+
+    #[test]
+    fn test_unassigned_logins() {
+        let issue = Issue {
+            number: 1,
+            html_url: "https://github.com/uiur/sandbox/issues/1".to_string(),
+            title: "title".to_string(),
+            body: None,
+            state: "open".to_string(),
+            labels: vec![],
+            assignees: vec![User {
+                login: "uiur".to_string(),
+            }],
+        };
+
+        let logins = vec!["Uiur".to_string(), "outsider".to_string()];
+        assert_eq!(
+            issue.unassigned_logins(&logins),
+            vec!["outsider".to_string()]
+        );
+        assert!(issue.unassigned_logins(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_missing_labels() {
+        let labels = vec![
+            Label {
+                name: "bug".to_string(),
+            },
+            Label {
+                name: "Good First Issue".to_string(),
+            },
+        ];
+
+        let names = vec![
+            "bug".to_string(),
+            "good first issue".to_string(),
+            "wontfix".to_string(),
+        ];
+        assert_eq!(missing_labels(&names, &labels), vec!["wontfix".to_string()]);
+        assert!(missing_labels(&[], &labels).is_empty());
+    }
+
+    #[test]
+    fn test_parse_issue_url() {
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/issues/12"),
+            Some(("uiur/sandbox".to_string(), 12))
+        );
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/issues/12#issuecomment-1"),
+            Some(("uiur/sandbox".to_string(), 12))
+        );
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/issues/12/"),
+            Some(("uiur/sandbox".to_string(), 12))
+        );
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/pull/12"),
+            None
+        );
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/issues"),
+            None
+        );
+        assert_eq!(
+            parse_issue_url("https://github.com/uiur/sandbox/issues/abc"),
+            None
+        );
+        assert_eq!(
+            parse_issue_url("https://example.com/uiur/sandbox/issues/12"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_new_issue_params() {
+        let params = NewIssue {
+            title: "title".to_string(),
+            body: "body".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({ "title": "title", "body": "body" })
+        );
+
+        let params = NewIssue {
+            labels: vec!["bug".to_string()],
+            milestone: Some(3),
+            issue_type: Some("Bug".to_string()),
+            ..params
+        };
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({
+                "title": "title",
+                "body": "body",
+                "labels": ["bug"],
+                "milestone": 3,
+                "type": "Bug"
+            })
+        );
+    }
 }
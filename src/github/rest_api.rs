@@ -0,0 +1,269 @@
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::http_client::{HttpClient, RequestBuilder};
+
+use super::{
+    app, error, GithubClientError, Installation, Issue, IssueComment, IssueTracker, Label,
+    NewIssue, User,
+};
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+const LABELS_PER_PAGE: usize = 100;
+
+/// `IssueTracker` over HTTP. `base_url` is the root of the REST API, e.g. `https://api.github.com`.
+pub struct GithubRestApi {
+    client: HttpClient,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct ListUserInstallationResponse {
+    installations: Vec<Installation>,
+}
+
+impl GithubRestApi {
+    pub fn new(client: HttpClient, base_url: &str) -> Self {
+        GithubRestApi {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        self.client.get(format!("{}{}", self.base_url, path))
+    }
+
+    pub(super) fn post(&self, path: &str) -> RequestBuilder {
+        self.client.post(format!("{}{}", self.base_url, path))
+    }
+
+    fn patch(&self, path: &str) -> RequestBuilder {
+        self.client.patch(format!("{}{}", self.base_url, path))
+    }
+
+    async fn post_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        params: &NewIssue,
+    ) -> Result<Issue, GithubClientError> {
+        let request = self
+            .post(&format!("/repos/{}/issues", repo))
+            .header("Content-Type", "application/json")
+            .json(params);
+
+        send_once(request, token).await
+    }
+}
+
+pub(super) async fn send<T: DeserializeOwned>(
+    request: RequestBuilder,
+    token: &str,
+) -> Result<T, GithubClientError> {
+    send_request(request, token, true).await
+}
+
+// Filing an issue or a comment that timed out may still have gone through, so it isn't retried
+// unless it never reached GitHub.
+async fn send_once<T: DeserializeOwned>(
+    request: RequestBuilder,
+    token: &str,
+) -> Result<T, GithubClientError> {
+    send_request(request, token, false).await
+}
+
+async fn send_request<T: DeserializeOwned>(
+    request: RequestBuilder,
+    token: &str,
+    repeatable: bool,
+) -> Result<T, GithubClientError> {
+    let request_error = |e: reqwest::Error| GithubClientError::Request {
+        message: e.to_string(),
+        timeout: e.is_timeout(),
+        retryable: repeatable || e.is_connect(),
+    };
+    let resp = request
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("User-Agent", "uiur/emoji-to-do")
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(request_error)?;
+
+    log::debug!("{:#?}", resp);
+    let status = resp.status();
+    let body = resp.text().await.map_err(request_error)?;
+    if !status.is_success() {
+        log::error!("{}", body);
+    }
+
+    error::parse_response(status, body)
+}
+
+#[async_trait]
+impl IssueTracker for GithubRestApi {
+    async fn installation_token(&self, installation_id: i32) -> Result<String, GithubClientError> {
+        app::installation_token(self, installation_id).await
+    }
+
+    // Assignees who can't be assigned (e.g. non-collaborators) make GitHub reject the whole
+    // request, so the issue is retried without them.
+    async fn create_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        params: &NewIssue,
+    ) -> Result<Issue, GithubClientError> {
+        match self.post_issue(token, repo, params).await {
+            Err(GithubClientError::Api { status: 422, .. }) if !params.assignees.is_empty() => {
+                let params = NewIssue {
+                    assignees: vec![],
+                    ..params.clone()
+                };
+                self.post_issue(token, repo, &params).await
+            }
+            result => result,
+        }
+    }
+
+    async fn create_comment(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        body: &str,
+    ) -> Result<IssueComment, GithubClientError> {
+        let request = self
+            .post(&format!("/repos/{}/issues/{}/comments", repo, issue_number))
+            .json(&serde_json::json!({ "body": body }));
+
+        send_once(request, token).await
+    }
+
+    async fn get_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+    ) -> Result<Issue, GithubClientError> {
+        let request = self.get(&format!("/repos/{}/issues/{}", repo, issue_number));
+
+        send(request, token).await
+    }
+
+    // https://docs.github.com/en/rest/issues/issues#update-an-issue
+    async fn update_issue(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        params: &serde_json::Value,
+    ) -> Result<Issue, GithubClientError> {
+        let request = self
+            .patch(&format!("/repos/{}/issues/{}", repo, issue_number))
+            .json(params);
+
+        send(request, token).await
+    }
+
+    async fn add_assignees(
+        &self,
+        token: &str,
+        repo: &str,
+        issue_number: i32,
+        logins: &[String],
+    ) -> Result<Issue, GithubClientError> {
+        let request = self
+            .post(&format!(
+                "/repos/{}/issues/{}/assignees",
+                repo, issue_number
+            ))
+            .json(&serde_json::json!({ "assignees": logins }));
+
+        send(request, token).await
+    }
+
+    async fn list_labels(&self, token: &str, repo: &str) -> Result<Vec<Label>, GithubClientError> {
+        let mut labels = vec![];
+        for page in 1.. {
+            let request = self
+                .get(&format!("/repos/{}/labels", repo))
+                .query(&[("per_page", LABELS_PER_PAGE), ("page", page)]);
+            let page_labels: Vec<Label> = send(request, token).await?;
+            let is_last_page = page_labels.len() < LABELS_PER_PAGE;
+            labels.extend(page_labels);
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(labels)
+    }
+
+    async fn list_user_installations(
+        &self,
+        token: &str,
+    ) -> Result<Vec<Installation>, GithubClientError> {
+        let request = self
+            .get("/user/installations")
+            .header("Content-Type", "application/json");
+        let result: ListUserInstallationResponse = send(request, token).await?;
+        Ok(result.installations)
+    }
+
+    // https://docs.github.com/en/rest/users/users#get-the-authenticated-user
+    async fn get_authenticated_user(&self, token: &str) -> Result<User, GithubClientError> {
+        send(self.get("/user"), token).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, net::TcpListener, thread};
+
+    use super::GithubRestApi;
+    use crate::{github::IssueTracker, http_client::HttpClient};
+
+    // Reads each request and hangs up without answering, like a connection dropped by GitHub
+    fn start_hanging_up_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut stream = stream;
+                let _ = stream.read(&mut [0; 4096]);
+            }
+        });
+        url
+    }
+
+    #[actix_rt::test]
+    async fn test_dropped_requests_are_retried_only_when_repeatable() {
+        let github = GithubRestApi::new(HttpClient::new(), &start_hanging_up_server());
+
+        let e = github
+            .create_comment("token", "uiur/sandbox", 1, "+1")
+            .await
+            .err()
+            .unwrap();
+        assert!(!e.is_retryable(), "{}", e);
+
+        let e = github
+            .get_issue("token", "uiur/sandbox", 1)
+            .await
+            .err()
+            .unwrap();
+        assert!(e.is_retryable(), "{}", e);
+
+        // Nothing was sent when the connection failed
+        let closed = GithubRestApi::new(HttpClient::new(), "http://127.0.0.1:1");
+        let e = closed
+            .create_comment("token", "uiur/sandbox", 1, "+1")
+            .await
+            .err()
+            .unwrap();
+        assert!(e.is_retryable(), "{}", e);
+    }
+}
@@ -0,0 +1,225 @@
+use serde::Deserialize;
+
+use crate::slack::escape;
+
+// https://docs.github.com/en/webhooks/webhook-events-and-payloads#issues
+#[derive(Deserialize, Debug)]
+pub struct IssuesEvent {
+    pub action: String,
+    pub issue: WebhookIssue,
+    pub repository: Repository,
+    pub sender: Sender,
+    pub assignee: Option<Sender>,
+}
+
+// https://docs.github.com/en/webhooks/webhook-events-and-payloads#issue_comment
+#[derive(Deserialize, Debug)]
+pub struct IssueCommentEvent {
+    pub action: String,
+    pub issue: WebhookIssue,
+    pub comment: Comment,
+    pub repository: Repository,
+    pub sender: Sender,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookIssue {
+    pub number: i32,
+    pub title: String,
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Comment {
+    pub html_url: String,
+    pub body: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Repository {
+    pub full_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Sender {
+    pub login: String,
+    #[serde(rename = "type", default)]
+    pub account_type: String,
+}
+
+impl Sender {
+    pub fn is_bot(&self) -> bool {
+        self.account_type == "Bot"
+    }
+}
+
+/// A change of an issue worth telling the Slack thread it came from.
+#[derive(Debug, PartialEq)]
+pub struct IssueUpdate {
+    pub repo: String,
+    pub issue_number: i32,
+    pub text: String,
+    pub closed: bool,
+}
+
+// Comments are quoted in Slack up to this many characters
+const COMMENT_EXCERPT_CHARS: usize = 300;
+
+impl IssuesEvent {
+    pub fn update(&self) -> Option<IssueUpdate> {
+        let link = issue_link(&self.issue);
+        let text = match self.action.as_str() {
+            "closed" => format!("{} was closed by @{}", link, self.sender.login),
+            "reopened" => format!("{} was reopened by @{}", link, self.sender.login),
+            "assigned" => format!(
+                "{} was assigned to @{}",
+                link,
+                self.assignee.as_ref()?.login
+            ),
+            _ => return None,
+        };
+
+        Some(IssueUpdate {
+            repo: self.repository.full_name.clone(),
+            issue_number: self.issue.number,
+            text,
+            closed: self.action == "closed",
+        })
+    }
+}
+
+impl IssueCommentEvent {
+    pub fn update(&self) -> Option<IssueUpdate> {
+        // Comments of the app itself are echoes of Slack, e.g. repeated reactions
+        if self.action != "created" || self.sender.is_bot() {
+            return None;
+        }
+
+        let mut excerpt: String = self
+            .comment
+            .body
+            .chars()
+            .take(COMMENT_EXCERPT_CHARS)
+            .collect();
+        if excerpt.len() < self.comment.body.len() {
+            excerpt.push('…');
+        }
+        let quote: Vec<String> = excerpt
+            .lines()
+            .map(|line| format!("> {}", escape(line)))
+            .collect();
+
+        Some(IssueUpdate {
+            repo: self.repository.full_name.clone(),
+            issue_number: self.issue.number,
+            text: format!(
+                "@{} <{}|commented> on {}\n{}",
+                self.sender.login,
+                self.comment.html_url,
+                issue_link(&self.issue),
+                quote.join("\n")
+            ),
+            closed: false,
+        })
+    }
+}
+
+fn issue_link(issue: &WebhookIssue) -> String {
+    format!(
+        "<{}|#{} {}>",
+        issue.html_url,
+        issue.number,
+        escape(&issue.title)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{IssueCommentEvent, IssueUpdate, IssuesEvent};
+
+    fn issues_event(action: &str) -> serde_json::Value {
+        json!({
+            "action": action,
+            "issue": {
+                "number": 12,
+                "title": "build <main> fails",
+                "html_url": "https://github.com/uiur/sandbox/issues/12"
+            },
+            "repository": { "full_name": "uiur/sandbox" },
+            "sender": { "login": "uiur", "type": "User" },
+            "assignee": { "login": "octocat", "type": "User" }
+        })
+    }
+
+    #[test]
+    fn test_issues_event_update() {
+        let event: IssuesEvent = serde_json::from_value(issues_event("closed")).unwrap();
+        assert_eq!(
+            event.update(),
+            Some(IssueUpdate {
+                repo: "uiur/sandbox".to_string(),
+                issue_number: 12,
+                text: "<https://github.com/uiur/sandbox/issues/12|#12 build &lt;main&gt; fails> was closed by @uiur".to_string(),
+                closed: true,
+            })
+        );
+
+        let event: IssuesEvent = serde_json::from_value(issues_event("reopened")).unwrap();
+        let update = event.update().unwrap();
+        assert!(update.text.ends_with("was reopened by @uiur"));
+        assert!(!update.closed);
+
+        let event: IssuesEvent = serde_json::from_value(issues_event("assigned")).unwrap();
+        assert!(event
+            .update()
+            .unwrap()
+            .text
+            .ends_with("was assigned to @octocat"));
+
+        let event: IssuesEvent = serde_json::from_value(issues_event("labeled")).unwrap();
+        assert_eq!(event.update(), None);
+    }
+
+    #[test]
+    fn test_issue_comment_event_update() {
+        let mut value = json!({
+            "action": "created",
+            "issue": {
+                "number": 12,
+                "title": "build fails",
+                "html_url": "https://github.com/uiur/sandbox/issues/12"
+            },
+            "comment": {
+                "html_url": "https://github.com/uiur/sandbox/issues/12#issuecomment-1",
+                "body": "fixed in #13\nplease check"
+            },
+            "repository": { "full_name": "uiur/sandbox" },
+            "sender": { "login": "uiur", "type": "User" }
+        });
+
+        let event: IssueCommentEvent = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            event.update().unwrap().text,
+            "@uiur <https://github.com/uiur/sandbox/issues/12#issuecomment-1|commented> on <https://github.com/uiur/sandbox/issues/12|#12 build fails>\n> fixed in #13\n> please check"
+        );
+
+        value["comment"]["body"] = json!("a".repeat(400));
+        let event: IssueCommentEvent = serde_json::from_value(value.clone()).unwrap();
+        assert!(event
+            .update()
+            .unwrap()
+            .text
+            .ends_with(&format!("> {}…", "a".repeat(300))));
+
+        value["sender"] = json!({ "login": "emoji-to-do[bot]", "type": "Bot" });
+        let event: IssueCommentEvent = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(event.update(), None);
+
+        value["sender"] = json!({ "login": "uiur", "type": "User" });
+        value["action"] = json!("edited");
+        let event: IssueCommentEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(event.update(), None);
+    }
+}
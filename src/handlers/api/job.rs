@@ -0,0 +1,98 @@
+use actix_web::{
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::{entities, jobs};
+
+use super::get_current_user;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JobResponse {
+    id: i32,
+    status: String,
+    attempts: i32,
+    run_at: i64,
+    last_error: Option<String>,
+    payload: serde_json::Value,
+    created_at: String,
+}
+
+impl From<entities::job::Model> for JobResponse {
+    fn from(job: entities::job::Model) -> Self {
+        JobResponse {
+            id: job.id,
+            status: job.status,
+            attempts: job.attempts,
+            run_at: job.run_at,
+            last_error: job.last_error,
+            payload: serde_json::from_str(&job.payload).unwrap_or_default(),
+            created_at: job.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GetJobsQuery {
+    status: Option<String>,
+}
+
+pub async fn get_jobs(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    query: web::Query<GetJobsQuery>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let user = get_current_user(&connection, &req)
+        .await
+        .ok_or_else(|| ErrorUnauthorized(""))?;
+
+    let mut select = entities::prelude::Job::find()
+        .filter(entities::job::Column::SlackTeamId.eq(user.slack_team_id.as_str()))
+        .order_by_asc(entities::job::Column::Id);
+    if let Some(status) = &query.status {
+        select = select.filter(entities::job::Column::Status.eq(status.as_str()));
+    }
+
+    let result: Vec<JobResponse> = select
+        .all(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?
+        .into_iter()
+        .map(JobResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+pub async fn retry_job(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    path: web::Path<(i32,)>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let user = get_current_user(&connection, &req)
+        .await
+        .ok_or_else(|| ErrorUnauthorized(""))?;
+
+    let (job_id,) = path.into_inner();
+    let job = entities::prelude::Job::find_by_id(job_id)
+        .one(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("job is not found"))?;
+
+    if job.slack_team_id != user.slack_team_id {
+        return Err(ErrorNotFound("job is not found"));
+    }
+
+    if job.status != jobs::STATUS_DEAD {
+        return Err(ErrorBadRequest("only failed jobs can be retried"));
+    }
+
+    let job = jobs::retry(connection.as_ref(), job)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(JobResponse::from(job)))
+}
@@ -9,12 +9,14 @@ use crate::entities;
 
 use self::user::get_user;
 
+pub mod job;
 pub mod reaction;
 pub mod reaction_assignee;
 pub mod session;
 pub mod team;
 pub mod token;
 pub mod user;
+pub mod user_link;
 
 #[derive(Serialize, Deserialize)]
 struct JwtBody {
@@ -1,7 +1,7 @@
-use std::{collections::HashSet, option};
+use std::collections::{HashMap, HashSet};
 
 use actix_web::{
-    error::{ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
     web, HttpRequest, HttpResponse, Responder,
 };
 
@@ -10,16 +10,29 @@ use sea_orm::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::entities::{self, reaction_assignee};
+use crate::{
+    entities,
+    github::{self, IssueTracker},
+    issue_template,
+    slack::ReplyMode,
+};
 
 use super::get_current_user;
 
 #[derive(Debug, Serialize, Deserialize)]
-struct ReactionResponse {
-    id: i32,
-    name: String,
-    repo: String,
-    reaction_assignees: Vec<entities::reaction_assignee::Model>,
+pub struct ReactionResponse {
+    pub id: i32,
+    pub name: String,
+    pub repo: String,
+    pub include_thread: bool,
+    pub title_template: Option<String>,
+    pub body_template: Option<String>,
+    pub milestone: Option<i32>,
+    pub issue_type: Option<String>,
+    pub reply_mode: Option<String>,
+    pub confirm: bool,
+    pub reaction_assignees: Vec<entities::reaction_assignee::Model>,
+    pub reaction_labels: Vec<entities::reaction_label::Model>,
 }
 
 pub async fn get_reactions(
@@ -42,31 +55,107 @@ pub async fn get_reactions(
         return Err(ErrorNotFound("team is not found"));
     }
 
+    let result = find_reactions(connection.as_ref(), &team).await?;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Returns the rules of the team with their assignees and labels.
+pub async fn find_reactions(
+    connection: &sea_orm::DatabaseConnection,
+    team: &entities::team::Model,
+) -> actix_web::Result<Vec<ReactionResponse>> {
     let reactions = team
         .find_related(entities::prelude::Reaction)
         .find_with_related(entities::prelude::ReactionAssignee)
-        .all(connection.as_ref())
+        .all(connection)
         .await
         .map_err(ErrorInternalServerError)?;
 
-    let result: Vec<ReactionResponse> = reactions
+    let mut reaction_labels_map: HashMap<i32, Vec<entities::reaction_label::Model>> =
+        HashMap::new();
+    for reaction_label in entities::prelude::ReactionLabel::find()
+        .filter(
+            entities::reaction_label::Column::ReactionId
+                .is_in(reactions.iter().map(|(reaction, _)| reaction.id)),
+        )
+        .all(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        reaction_labels_map
+            .entry(reaction_label.reaction_id)
+            .or_default()
+            .push(reaction_label);
+    }
+
+    let result = reactions
         .into_iter()
         .map(|(reaction, reaction_assignees)| ReactionResponse {
             id: reaction.id,
             name: reaction.name.clone(),
             repo: reaction.repo.clone(),
+            include_thread: reaction.include_thread,
+            title_template: reaction.title_template.clone(),
+            body_template: reaction.body_template.clone(),
+            milestone: reaction.milestone,
+            issue_type: reaction.issue_type.clone(),
+            reply_mode: reaction.reply_mode.clone(),
+            confirm: reaction.confirm,
             reaction_assignees,
+            reaction_labels: reaction_labels_map.remove(&reaction.id).unwrap_or_default(),
         })
         .collect();
 
-    Ok(HttpResponse::Ok().json(result))
+    Ok(result)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateReactionRequestBody {
     pub name: String,
     pub repo: String,
+    #[serde(default = "default_include_thread")]
+    pub include_thread: bool,
+    #[serde(default)]
+    pub title_template: Option<String>,
+    #[serde(default)]
+    pub body_template: Option<String>,
+    #[serde(default)]
+    pub milestone: Option<i32>,
+    #[serde(default)]
+    pub issue_type: Option<String>,
+    /// Overrides the team's reply mode
+    #[serde(default)]
+    pub reply_mode: Option<ReplyMode>,
+    /// Lets the reactioner edit the issue in a modal before it is filed
+    #[serde(default)]
+    pub confirm: bool,
     pub reaction_assignees: Vec<CreateReactionRequestReactionAssignee>,
+    #[serde(default)]
+    pub reaction_labels: Vec<CreateReactionRequestReactionLabel>,
+}
+
+fn default_include_thread() -> bool {
+    true
+}
+
+impl CreateReactionRequestBody {
+    /// A rule with the same defaults as an API request leaving out optional fields.
+    pub fn new(name: String, repo: String) -> Self {
+        CreateReactionRequestBody {
+            name,
+            repo,
+            include_thread: default_include_thread(),
+            title_template: None,
+            body_template: None,
+            milestone: None,
+            issue_type: None,
+            reply_mode: None,
+            confirm: false,
+            reaction_assignees: vec![],
+            reaction_labels: vec![],
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,34 +163,117 @@ pub struct CreateReactionRequestReactionAssignee {
     pub name: String,
 }
 
-pub async fn create_reaction(
-    connection: web::Data<sea_orm::DatabaseConnection>,
-    path: web::Path<(i32,)>,
-    req: HttpRequest,
-    body: web::Json<CreateReactionRequestBody>,
-) -> actix_web::Result<impl Responder> {
-    let user = get_current_user(&connection, &req)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateReactionRequestReactionLabel {
+    pub name: String,
+}
+
+// Labels are checked against the repository when the rule is saved, because GitHub silently
+// creates unknown labels on new issues.
+async fn validate_labels(
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    repo: &str,
+    reaction_labels: &[CreateReactionRequestReactionLabel],
+) -> actix_web::Result<()> {
+    if reaction_labels.is_empty() {
+        return Ok(());
+    }
+
+    let installation_id = team
+        .github_installation_id
+        .ok_or_else(|| ErrorBadRequest("github app is not installed"))?;
+    let github_token = issue_tracker.installation_token(installation_id).await?;
+    let labels = issue_tracker.list_labels(&github_token, repo).await?;
+
+    let names: Vec<String> = reaction_labels
+        .iter()
+        .map(|reaction_label| reaction_label.name.clone())
+        .collect();
+    let missing_labels = github::missing_labels(&names, &labels);
+    if !missing_labels.is_empty() {
+        return Err(ErrorBadRequest(format!(
+            "labels are not found in {}: {}",
+            repo,
+            missing_labels.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+async fn save_reaction_labels(
+    connection: &sea_orm::DatabaseConnection,
+    reaction: &entities::reaction::Model,
+    reaction_labels: &[CreateReactionRequestReactionLabel],
+) -> actix_web::Result<()> {
+    let names: Vec<String> = reaction_labels
+        .iter()
+        .map(|reaction_label| reaction_label.name.clone())
+        .collect();
+
+    entities::prelude::ReactionLabel::delete_many()
+        .filter(entities::reaction_label::Column::ReactionId.eq(reaction.id))
+        .filter(entities::reaction_label::Column::Name.is_not_in(names.clone()))
+        .exec(connection)
         .await
-        .ok_or_else(|| ErrorUnauthorized(""))?;
+        .map_err(ErrorInternalServerError)?;
 
-    let (team_id,) = path.into_inner();
-    let team = entities::prelude::Team::find_by_id(team_id)
-        .one(connection.as_ref())
+    let existing_names: HashSet<String> = reaction
+        .find_related(entities::prelude::ReactionLabel)
+        .all(connection)
         .await
         .map_err(ErrorInternalServerError)?
-        .ok_or_else(|| ErrorNotFound("team is not found"))?;
+        .into_iter()
+        .map(|reaction_label| reaction_label.name)
+        .collect();
 
-    if user.slack_team_id != team.slack_team_id {
-        return Err(ErrorNotFound("team is not found"));
+    for name in names {
+        if existing_names.contains(&name) {
+            continue;
+        }
+
+        entities::reaction_label::ActiveModel {
+            reaction_id: Set(reaction.id),
+            name: Set(name),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
     }
 
+    Ok(())
+}
+
+/// Creates a rule of the team, after validating it the same way as updates.
+pub async fn insert_reaction(
+    connection: &sea_orm::DatabaseConnection,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    body: &CreateReactionRequestBody,
+) -> actix_web::Result<entities::reaction::Model> {
+    issue_template::validate(
+        body.title_template.as_deref(),
+        body.body_template.as_deref(),
+    )
+    .map_err(ErrorBadRequest)?;
+    validate_labels(issue_tracker, team, &body.repo, &body.reaction_labels).await?;
+
     let reaction = entities::reaction::ActiveModel {
         team_id: Set(team.id),
         name: Set(body.name.clone()),
         repo: Set(body.repo.clone()),
+        include_thread: Set(body.include_thread),
+        title_template: Set(body.title_template.clone()),
+        body_template: Set(body.body_template.clone()),
+        milestone: Set(body.milestone),
+        issue_type: Set(body.issue_type.clone()),
+        reply_mode: Set(body.reply_mode.map(|mode| mode.as_str().to_owned())),
+        confirm: Set(body.confirm),
         ..Default::default()
     }
-    .save(connection.as_ref())
+    .save(connection)
     .await
     .map_err(ErrorInternalServerError)?;
     let reaction_id = reaction.id.unwrap();
@@ -112,16 +284,45 @@ pub async fn create_reaction(
             name: Set(body.name.clone()),
             ..Default::default()
         }
-        .insert(connection.as_ref())
+        .insert(connection)
         .await
         .map_err(ErrorInternalServerError)?;
     }
 
     let reaction = entities::prelude::Reaction::find_by_id(reaction_id)
-        .one(connection.as_ref())
+        .one(connection)
         .await
         .map_err(ErrorInternalServerError)?
         .ok_or_else(|| ErrorNotFound("reaction is not found"))?;
+    save_reaction_labels(connection, &reaction, &body.reaction_labels).await?;
+
+    Ok(reaction)
+}
+
+pub async fn create_reaction(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    issue_tracker: web::Data<dyn IssueTracker>,
+    path: web::Path<(i32,)>,
+    req: HttpRequest,
+    body: web::Json<CreateReactionRequestBody>,
+) -> actix_web::Result<impl Responder> {
+    let user = get_current_user(&connection, &req)
+        .await
+        .ok_or_else(|| ErrorUnauthorized(""))?;
+
+    let (team_id,) = path.into_inner();
+    let team = entities::prelude::Team::find_by_id(team_id)
+        .one(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("team is not found"))?;
+
+    if user.slack_team_id != team.slack_team_id {
+        return Err(ErrorNotFound("team is not found"));
+    }
+
+    let reaction =
+        insert_reaction(connection.as_ref(), issue_tracker.as_ref(), &team, &body).await?;
 
     Ok(HttpResponse::Created().json(reaction))
 }
@@ -159,11 +360,25 @@ pub async fn get_reaction(
         .await
         .map_err(ErrorInternalServerError)?;
 
+    let reaction_labels = reaction
+        .find_related(entities::prelude::ReactionLabel)
+        .all(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
+
     Ok(HttpResponse::Ok().json(ReactionResponse {
         id: reaction.id,
         name: reaction.name,
         repo: reaction.repo,
+        include_thread: reaction.include_thread,
+        title_template: reaction.title_template,
+        body_template: reaction.body_template,
+        milestone: reaction.milestone,
+        issue_type: reaction.issue_type,
+        reply_mode: reaction.reply_mode,
+        confirm: reaction.confirm,
         reaction_assignees,
+        reaction_labels,
     }))
 }
 
@@ -171,6 +386,7 @@ pub type UpdateReactionRequestBody = CreateReactionRequestBody;
 
 pub async fn put_reaction(
     connection: web::Data<sea_orm::DatabaseConnection>,
+    issue_tracker: web::Data<dyn IssueTracker>,
     path: web::Path<(i32,)>,
     req: HttpRequest,
     body: web::Json<UpdateReactionRequestBody>,
@@ -197,9 +413,29 @@ pub async fn put_reaction(
         return Err(ErrorNotFound("reaction is not found"));
     }
 
+    issue_template::validate(
+        body.title_template.as_deref(),
+        body.body_template.as_deref(),
+    )
+    .map_err(ErrorBadRequest)?;
+    validate_labels(
+        issue_tracker.as_ref(),
+        &team,
+        &body.repo,
+        &body.reaction_labels,
+    )
+    .await?;
+
     let mut active_model = reaction.into_active_model();
     active_model.name = Set(body.name.clone());
     active_model.repo = Set(body.repo.clone());
+    active_model.include_thread = Set(body.include_thread);
+    active_model.title_template = Set(body.title_template.clone());
+    active_model.body_template = Set(body.body_template.clone());
+    active_model.milestone = Set(body.milestone);
+    active_model.issue_type = Set(body.issue_type.clone());
+    active_model.reply_mode = Set(body.reply_mode.map(|mode| mode.as_str().to_owned()));
+    active_model.confirm = Set(body.confirm);
 
     active_model
         .save(connection.as_ref())
@@ -258,6 +494,7 @@ pub async fn put_reaction(
         .exec(connection.as_ref())
         .await
         .map_err(ErrorInternalServerError)?;
+    save_reaction_labels(connection.as_ref(), &reaction, &body.reaction_labels).await?;
 
     Ok(HttpResponse::Ok().json(reaction))
 }
@@ -295,3 +532,28 @@ pub async fn destroy_reaction(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewReactionRequestBody {
+    pub title_template: Option<String>,
+    pub body_template: Option<String>,
+}
+
+pub async fn preview_reaction(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    req: HttpRequest,
+    body: web::Json<PreviewReactionRequestBody>,
+) -> actix_web::Result<impl Responder> {
+    get_current_user(&connection, &req)
+        .await
+        .ok_or_else(|| ErrorUnauthorized(""))?;
+
+    let issue = issue_template::render(
+        body.title_template.as_deref(),
+        body.body_template.as_deref(),
+        &issue_template::sample_context(),
+    )
+    .map_err(ErrorBadRequest)?;
+
+    Ok(HttpResponse::Ok().json(issue))
+}
@@ -2,10 +2,10 @@ use actix_web::{
     error::{ErrorInternalServerError, ErrorNotFound},
     web, HttpRequest, HttpResponse, Responder,
 };
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use serde::Serialize;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
 
-use crate::entities;
+use crate::{entities, slack::ReplyMode};
 
 use super::get_current_user;
 
@@ -15,29 +15,75 @@ struct TeamResponse {
     name: String,
     slack_team_id: String,
     github_installation_id: Option<i32>,
+    closed_issue_reaction: Option<String>,
+    reply_mode: String,
 }
 
-pub async fn get_team(
-    connection: web::Data<sea_orm::DatabaseConnection>,
-    req: HttpRequest,
-) -> actix_web::Result<impl Responder> {
-    let user = get_current_user(connection.as_ref(), &req)
+impl From<entities::team::Model> for TeamResponse {
+    fn from(team: entities::team::Model) -> Self {
+        TeamResponse {
+            id: team.id,
+            name: team.name,
+            slack_team_id: team.slack_team_id,
+            github_installation_id: team.github_installation_id,
+            closed_issue_reaction: team.closed_issue_reaction,
+            reply_mode: team.reply_mode,
+        }
+    }
+}
+
+async fn find_current_team(
+    connection: &sea_orm::DatabaseConnection,
+    req: &HttpRequest,
+) -> actix_web::Result<entities::team::Model> {
+    let user = get_current_user(connection, req)
         .await
         .ok_or_else(|| ErrorNotFound("user is not found"))?;
 
-    let team = entities::prelude::Team::find()
+    entities::prelude::Team::find()
         .filter(entities::team::Column::SlackTeamId.eq(user.slack_team_id.as_str()))
-        .one(connection.as_ref())
+        .one(connection)
         .await
         .map_err(ErrorInternalServerError)?
-        .ok_or_else(|| ErrorNotFound("team is not found"))?;
+        .ok_or_else(|| ErrorNotFound("team is not found"))
+}
 
-    let team_response = TeamResponse {
-        id: team.id,
-        name: team.name,
-        slack_team_id: team.slack_team_id,
-        github_installation_id: team.github_installation_id,
-    };
+pub async fn get_team(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let team = find_current_team(connection.as_ref(), &req).await?;
+
+    Ok(HttpResponse::Ok().json(TeamResponse::from(team)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeamRequestBody {
+    /// Emoji name added to the reacted message when its issue is closed. `null` resets it to
+    /// the default.
+    pub closed_issue_reaction: Option<String>,
+    /// How the app replies to reactioners, unless a rule says otherwise
+    #[serde(default)]
+    pub reply_mode: ReplyMode,
+}
+
+pub async fn put_team(
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    req: HttpRequest,
+    body: web::Json<UpdateTeamRequestBody>,
+) -> actix_web::Result<impl Responder> {
+    let team = find_current_team(connection.as_ref(), &req).await?;
+
+    let mut active_model = team.into_active_model();
+    active_model.closed_issue_reaction = Set(body
+        .closed_issue_reaction
+        .as_deref()
+        .map(|name| name.trim_matches(':').to_owned()));
+    active_model.reply_mode = Set(body.reply_mode.as_str().to_owned());
+    let team = active_model
+        .update(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
 
-    Ok(HttpResponse::Ok().json(team_response))
+    Ok(HttpResponse::Ok().json(TeamResponse::from(team)))
 }
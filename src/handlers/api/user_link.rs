@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    ModelTrait, QueryFilter, Set,
+};
+use serde::Deserialize;
+
+use crate::entities;
+
+use super::get_current_user;
+
+async fn find_team(
+    connection: &DatabaseConnection,
+    req: &HttpRequest,
+    team_id: i32,
+) -> actix_web::Result<entities::team::Model> {
+    let user = get_current_user(connection, req)
+        .await
+        .ok_or_else(|| ErrorUnauthorized(""))?;
+
+    let team = entities::prelude::Team::find_by_id(team_id)
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("team is not found"))?;
+
+    if team.slack_team_id != user.slack_team_id {
+        return Err(ErrorNotFound("team is not found"));
+    }
+
+    Ok(team)
+}
+
+/// Links a Slack user to a GitHub login, replacing the previous link.
+pub async fn upsert_user_link(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    slack_user_id: &str,
+    github_login: &str,
+) -> Result<entities::user_link::Model, DbErr> {
+    let existing = entities::prelude::UserLink::find()
+        .filter(entities::user_link::Column::TeamId.eq(team_id))
+        .filter(entities::user_link::Column::SlackUserId.eq(slack_user_id))
+        .one(connection)
+        .await?;
+
+    match existing {
+        Some(user_link) => {
+            let mut active_model = user_link.into_active_model();
+            active_model.github_login = Set(github_login.to_owned());
+            active_model.update(connection).await
+        }
+        None => {
+            entities::user_link::ActiveModel {
+                team_id: Set(team_id),
+                slack_user_id: Set(slack_user_id.to_owned()),
+                github_login: Set(github_login.to_owned()),
+                ..Default::default()
+            }
+            .insert(connection)
+            .await
+        }
+    }
+}
+
+/// Returns the GitHub logins of the linked users among `slack_user_ids`.
+pub async fn find_github_logins(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    slack_user_ids: &[&str],
+) -> Result<HashMap<String, String>, DbErr> {
+    let user_links = entities::prelude::UserLink::find()
+        .filter(entities::user_link::Column::TeamId.eq(team_id))
+        .filter(entities::user_link::Column::SlackUserId.is_in(slack_user_ids.iter().copied()))
+        .all(connection)
+        .await?;
+
+    Ok(user_links
+        .into_iter()
+        .map(|user_link| (user_link.slack_user_id, user_link.github_login))
+        .collect())
+}
+
+// https://github.com/shinnn/github-username-regex
+fn is_github_login(login: &str) -> bool {
+    !login.is_empty()
+        && login.len() <= 39
+        && !login.starts_with('-')
+        && !login.ends_with('-')
+        && !login.contains("--")
+        && login.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+pub async fn get_user_links(
+    connection: web::Data<DatabaseConnection>,
+    path: web::Path<(i32,)>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let (team_id,) = path.into_inner();
+    let team = find_team(connection.as_ref(), &req, team_id).await?;
+
+    let user_links = team
+        .find_related(entities::prelude::UserLink)
+        .all(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(user_links))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutUserLinkRequestBody {
+    pub github_login: String,
+}
+
+pub async fn put_user_link(
+    connection: web::Data<DatabaseConnection>,
+    path: web::Path<(i32, String)>,
+    req: HttpRequest,
+    body: web::Json<PutUserLinkRequestBody>,
+) -> actix_web::Result<impl Responder> {
+    let (team_id, slack_user_id) = path.into_inner();
+    let team = find_team(connection.as_ref(), &req, team_id).await?;
+
+    let github_login = body.github_login.trim().trim_start_matches('@');
+    if !is_github_login(github_login) {
+        return Err(ErrorBadRequest(format!(
+            "{} is not a GitHub login",
+            body.github_login
+        )));
+    }
+
+    let user_link = upsert_user_link(connection.as_ref(), team.id, &slack_user_id, github_login)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(user_link))
+}
+
+pub async fn destroy_user_link(
+    connection: web::Data<DatabaseConnection>,
+    path: web::Path<(i32, String)>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let (team_id, slack_user_id) = path.into_inner();
+    let team = find_team(connection.as_ref(), &req, team_id).await?;
+
+    let result = entities::prelude::UserLink::delete_many()
+        .filter(entities::user_link::Column::TeamId.eq(team.id))
+        .filter(entities::user_link::Column::SlackUserId.eq(slack_user_id))
+        .exec(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    if result.rows_affected == 0 {
+        return Err(ErrorNotFound("user link is not found"));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -14,7 +14,11 @@ use oauth2::{
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
 
-use crate::{entities, github, handlers::api::get_current_user};
+use crate::{
+    entities,
+    github::IssueTracker,
+    handlers::api::{get_current_user, user_link::upsert_user_link},
+};
 
 type OauthClient = oauth2::Client<
     oauth2::StandardErrorResponse<oauth2::basic::BasicErrorResponseType>,
@@ -75,6 +79,7 @@ pub struct CallbackQuery {
 
 pub async fn github_auth_callback(
     connection: web::Data<sea_orm::DatabaseConnection>,
+    issue_tracker: web::Data<dyn IssueTracker>,
     query: Query<CallbackQuery>,
     _session: Session,
     req: HttpRequest,
@@ -110,7 +115,9 @@ pub async fn github_auth_callback(
     //         session.insert("user_id", user_id);
     //     }
     // }
-    let installations = github::list_user_installations(token.secret()).await?;
+    let installations = issue_tracker
+        .list_user_installations(token.secret())
+        .await?;
 
     let installation = installations
         .first()
@@ -121,12 +128,28 @@ pub async fn github_auth_callback(
         .ok_or_else(|| ErrorUnauthorized(""))?;
 
     let team = entities::prelude::Team::find()
-        .filter(entities::team::Column::SlackTeamId.eq(user.slack_team_id))
+        .filter(entities::team::Column::SlackTeamId.eq(user.slack_team_id.as_str()))
         .one(connection.as_ref())
         .await
         .map_err(ErrorInternalServerError)?
         .ok_or_else(|| ErrorNotFound("team is not found"))?;
 
+    // Lets issues filed from the user's messages and reactions mention them on GitHub. The
+    // installation doesn't depend on it, so it goes on without the link.
+    match issue_tracker.get_authenticated_user(token.secret()).await {
+        Ok(github_user) => {
+            upsert_user_link(
+                connection.as_ref(),
+                team.id,
+                &user.slack_user_id,
+                &github_user.login,
+            )
+            .await
+            .map_err(ErrorInternalServerError)?;
+        }
+        Err(e) => log::error!("failed to get the authenticated GitHub user: {}", e),
+    }
+
     let mut active_model = team.into_active_model();
     active_model.github_installation_id = Set(Some(installation.id));
     active_model
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use actix_web::{
+    error::{ErrorBadRequest, ErrorInternalServerError},
+    web, HttpRequest, HttpResponse, Responder,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+
+use crate::{
+    entities,
+    github::webhook::{IssueCommentEvent, IssueUpdate, IssuesEvent},
+    jobs::{self, Job},
+    slack::SlackApi,
+};
+
+use super::webhook::slack_client_for_team;
+
+const DEFAULT_CLOSED_ISSUE_REACTION: &str = "white_check_mark";
+
+pub async fn create_github_events(
+    req: HttpRequest,
+    body: web::Bytes,
+    connection: web::Data<sea_orm::DatabaseConnection>,
+) -> actix_web::Result<impl Responder> {
+    let event = req
+        .headers()
+        .get("X-GitHub-Event")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    log::debug!("github event: {}", event);
+
+    let update = match event {
+        "issues" => serde_json::from_slice::<IssuesEvent>(&body)
+            .map_err(ErrorBadRequest)?
+            .update(),
+        "issue_comment" => serde_json::from_slice::<IssueCommentEvent>(&body)
+            .map_err(ErrorBadRequest)?
+            .update(),
+        _ => None,
+    };
+
+    if let Some(update) = update {
+        enqueue_issue_update(connection.as_ref(), update)
+            .await
+            .map_err(ErrorInternalServerError)?;
+    }
+
+    Ok(HttpResponse::Ok().body(""))
+}
+
+// The same issue may be linked from several messages, even from several teams.
+async fn enqueue_issue_update(
+    connection: &DatabaseConnection,
+    update: IssueUpdate,
+) -> Result<(), DbErr> {
+    let message_issues = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::IssueNumber.eq(update.issue_number))
+        .find_also_related(entities::prelude::Team)
+        .all(connection)
+        .await?;
+
+    for (message_issue, team) in message_issues {
+        // Repository names are case-insensitive on GitHub
+        if !message_issue.repo.eq_ignore_ascii_case(&update.repo) {
+            continue;
+        }
+        let team = match team {
+            Some(team) => team,
+            None => continue,
+        };
+
+        jobs::enqueue(
+            connection,
+            &team.slack_team_id,
+            &Job::IssueUpdated {
+                message_issue_id: message_issue.id,
+                text: update.text.clone(),
+                closed: update.closed,
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn handle_issue_updated(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    message_issue_id: i32,
+    text: String,
+    closed: bool,
+) -> actix_web::Result<()> {
+    let (message_issue, team) = entities::prelude::MessageIssue::find_by_id(message_issue_id)
+        .find_also_related(entities::prelude::Team)
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorInternalServerError("message issue is not found"))?;
+    let team = team.ok_or_else(|| ErrorInternalServerError("team is not found"))?;
+    let slack_client = slack_client_for_team(slack_api, &team)?;
+
+    // A reply can't have replies of its own, so answer in the thread it belongs to
+    let thread_ts = slack_client
+        .get_replies(&message_issue.channel, &message_issue.ts, 1)
+        .await?
+        .into_iter()
+        .next()
+        .and_then(|message| message.thread_ts)
+        .unwrap_or_else(|| message_issue.ts.clone());
+
+    // Reacting again is a no-op, so the reply comes last and a retry doesn't post it twice
+    if closed {
+        let reaction = team
+            .closed_issue_reaction
+            .as_deref()
+            .unwrap_or(DEFAULT_CLOSED_ISSUE_REACTION);
+        slack_client
+            .add_reaction(&message_issue.channel, &message_issue.ts, reaction)
+            .await?;
+    }
+
+    slack_client
+        .post_message(&message_issue.channel, &text, &[], Some(&thread_ts))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::env;
+
+use actix_web::{
+    error::{ErrorNotFound, ErrorUnauthorized},
+    http::header,
+    web, HttpRequest, HttpResponse, Responder,
+};
+use openssl::memcmp;
+
+use crate::http_client::HttpClient;
+
+// Metrics tell which APIs and tokens are in use, so they are only served to a scraper holding
+// METRICS_TOKEN. Without it, there are no metrics.
+pub async fn get_metrics(
+    http_client: web::Data<HttpClient>,
+    req: HttpRequest,
+) -> actix_web::Result<impl Responder> {
+    let metrics_token = env::var("METRICS_TOKEN").unwrap_or_default();
+    if metrics_token.is_empty() {
+        return Err(ErrorNotFound(""));
+    }
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| {
+            token.len() == metrics_token.len()
+                && memcmp::eq(token.as_bytes(), metrics_token.as_bytes())
+        });
+    if !authorized {
+        return Err(ErrorUnauthorized(""));
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(http_client.render_metrics()))
+}
@@ -1,6 +1,11 @@
 pub mod api;
 pub mod github_auth;
+pub mod github_webhook;
 pub mod hello;
+pub mod metrics;
 pub mod root;
 pub mod slack_auth;
+pub mod slack_commands;
+pub mod slack_files;
+pub mod slack_interactions;
 pub mod webhook;
@@ -10,10 +10,10 @@ use oauth2::{
     AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
     TokenResponse, TokenUrl,
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
 
-use crate::entities;
+use crate::{entities, secret};
 
 type OauthClient = oauth2::Client<
     oauth2::StandardErrorResponse<oauth2::basic::BasicErrorResponseType>,
@@ -44,8 +44,26 @@ struct TeamFields {
 #[derive(Deserialize, Debug, Serialize)]
 struct AuthedUserFields {
     id: String,
+    access_token: Option<String>,
 }
 
+// Scopes of the bot token used to read reacted messages and reply to them
+const BOT_SCOPES: &[&str] = &[
+    "app_mentions:read",
+    "channels:history",
+    "channels:read",
+    "chat:write",
+    "commands",
+    "files:read",
+    "groups:history",
+    "groups:read",
+    "reactions:read",
+    "reactions:write",
+    "usergroups:read",
+    "users:read",
+    "users.profile:read",
+];
+
 fn create_oauth_client() -> OauthClient {
     let client_id = env::var("SLACK_CLIENT_ID").expect("SLACK_CLIENT_ID is expected");
     let client_secret = env::var("SLACK_CLIENT_SECRET").expect("SLACK_CLIENT_SECRET is expected");
@@ -71,7 +89,7 @@ pub async fn get_slack_auth() -> actix_web::Result<impl Responder> {
 
     let (auth_url, _csrf_token) = client
         .authorize_url(CsrfToken::new_random)
-        .add_scope(Scope::new("users.profile:read".to_string()))
+        .add_scopes(BOT_SCOPES.iter().map(|scope| Scope::new(scope.to_string())))
         .url();
 
     Ok(HttpResponse::Ok().json(GetSlackAuthResponse {
@@ -103,13 +121,43 @@ pub async fn slack_auth_callback(
         .await
         .map_err(actix_web::error::ErrorInternalServerError)?;
 
-    println!("{:?}", token_result.extra_fields());
     let extra_fields = token_result.extra_fields();
     let token = token_result.access_token();
 
     let slack_team_id = extra_fields.team.id.clone();
     let slack_user_id = extra_fields.authed_user.id.clone();
 
+    // The top-level access token is the bot token of the workspace the app was installed to
+    let slack_bot_token = secret::encrypt(token.secret()).map_err(ErrorInternalServerError)?;
+    let option_team = entities::prelude::Team::find()
+        .filter(entities::team::Column::SlackTeamId.eq(slack_team_id.as_str()))
+        .one(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    match option_team {
+        Some(team) => {
+            let mut active_model = team.into_active_model();
+            active_model.name = Set(extra_fields.team.name.clone());
+            active_model.slack_bot_token = Set(Some(slack_bot_token));
+            active_model
+                .save(connection.as_ref())
+                .await
+                .map_err(ErrorInternalServerError)?;
+        }
+        None => {
+            entities::team::ActiveModel {
+                name: Set(extra_fields.team.name.clone()),
+                slack_team_id: Set(slack_team_id.clone()),
+                slack_bot_token: Set(Some(slack_bot_token)),
+                ..Default::default()
+            }
+            .insert(connection.as_ref())
+            .await
+            .map_err(ErrorInternalServerError)?;
+        }
+    }
+
     let option_user = entities::prelude::User::find()
         .filter(entities::user::Column::SlackUserId.eq(slack_user_id.clone()))
         .one(connection.as_ref())
@@ -123,10 +171,17 @@ pub async fn slack_auth_callback(
             Ok(HttpResponse::Ok().json(SlackAuthCallbackResponse { token }))
         }
         None => {
+            let slack_token = extra_fields
+                .authed_user
+                .access_token
+                .as_deref()
+                .map(secret::encrypt)
+                .transpose()
+                .map_err(ErrorInternalServerError)?;
             let active_model = entities::user::ActiveModel {
                 slack_team_id: Set(slack_team_id),
                 slack_user_id: Set(slack_user_id),
-                slack_token: Set(token.secret().clone()),
+                slack_token: Set(slack_token),
                 ..Default::default()
             };
             let res = entities::user::Entity::insert(active_model)
@@ -0,0 +1,273 @@
+use actix_web::{error::ErrorInternalServerError, web, HttpResponse, Responder};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    entities,
+    github::IssueTracker,
+    slack::{blocks, escape},
+};
+
+use super::{
+    api::reaction::{
+        find_reactions, insert_reaction, CreateReactionRequestBody,
+        CreateReactionRequestReactionAssignee, ReactionResponse,
+    },
+    webhook::client_error_message,
+};
+
+// https://api.slack.com/interactivity/slash-commands#app_command_handling
+#[derive(Deserialize)]
+pub struct SlashCommandRequestBody {
+    team_id: String,
+    command: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum RuleCommand {
+    List,
+    Add {
+        name: String,
+        repo: String,
+        assignees: Vec<String>,
+    },
+    Remove {
+        name: String,
+    },
+    Help,
+}
+
+pub async fn create_slack_commands(
+    form: web::Form<SlashCommandRequestBody>,
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    issue_tracker: web::Data<dyn IssueTracker>,
+) -> actix_web::Result<impl Responder> {
+    let team = entities::prelude::Team::find()
+        .filter(entities::team::Column::SlackTeamId.eq(form.team_id.as_str()))
+        .one(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    let text = match team {
+        Some(team) => match parse_rule_command(&form.text) {
+            Ok(command) => {
+                run(
+                    connection.as_ref(),
+                    issue_tracker.as_ref(),
+                    &team,
+                    &form.command,
+                    command,
+                )
+                .await?
+            }
+            Err(message) => format!("{}\n\n{}", message, help(&form.command)),
+        },
+        None => "emoji-to-do is not installed to this workspace".to_string(),
+    };
+
+    Ok(HttpResponse::Ok().json(json!({
+        "response_type": "ephemeral",
+        "text": text,
+        "blocks": [blocks::section(&text)],
+    })))
+}
+
+async fn run(
+    connection: &DatabaseConnection,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    command_name: &str,
+    command: RuleCommand,
+) -> actix_web::Result<String> {
+    let text = match command {
+        RuleCommand::Help => help(command_name),
+
+        RuleCommand::List => {
+            let reactions = find_reactions(connection, team).await?;
+            if reactions.is_empty() {
+                return Ok(format!(
+                    "No rules yet. Add one with `{} add :emoji: owner/repo`",
+                    command_name
+                ));
+            }
+
+            format_rules(&reactions)
+        }
+
+        RuleCommand::Add {
+            name,
+            repo,
+            assignees,
+        } => {
+            let existing = entities::prelude::Reaction::find_by_slack_team_id_and_name(
+                &team.slack_team_id,
+                &name,
+            )
+            .one(connection)
+            .await
+            .map_err(ErrorInternalServerError)?;
+            if existing.is_some() {
+                return Ok(format!(":{}: already has a rule", name));
+            }
+
+            let mut body = CreateReactionRequestBody::new(name.clone(), repo.clone());
+            body.reaction_assignees = assignees
+                .into_iter()
+                .map(|name| CreateReactionRequestReactionAssignee { name })
+                .collect();
+
+            match insert_reaction(connection, issue_tracker, team, &body).await {
+                Ok(_) => format!(":{}: now files issues to {}", name, escape(&repo)),
+                Err(e) => format!(
+                    "failed to add :{}:: {}",
+                    name,
+                    client_error_message(&e).unwrap_or_else(|| e.to_string())
+                ),
+            }
+        }
+
+        RuleCommand::Remove { name } => {
+            let reaction = entities::prelude::Reaction::find_by_slack_team_id_and_name(
+                &team.slack_team_id,
+                &name,
+            )
+            .one(connection)
+            .await
+            .map_err(ErrorInternalServerError)?;
+
+            match reaction {
+                Some(reaction) => {
+                    entities::prelude::Reaction::delete_by_id(reaction.id)
+                        .exec(connection)
+                        .await
+                        .map_err(ErrorInternalServerError)?;
+                    format!("removed the rule of :{}:", name)
+                }
+                None => format!(":{}: has no rule", name),
+            }
+        }
+    };
+
+    Ok(text)
+}
+
+/// Lists the rules one per line, like `:bug: → uiur/bugs (@uiur)`.
+pub fn format_rules(reactions: &[ReactionResponse]) -> String {
+    let lines: Vec<String> = reactions
+        .iter()
+        .map(|reaction| {
+            let mut line = format!(":{}: → {}", reaction.name, escape(&reaction.repo));
+            if !reaction.reaction_assignees.is_empty() {
+                let assignees: Vec<String> = reaction
+                    .reaction_assignees
+                    .iter()
+                    .map(|assignee| format!("@{}", escape(&assignee.name)))
+                    .collect();
+                line.push_str(&format!(" ({})", assignees.join(", ")));
+            }
+            line
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn help(command_name: &str) -> String {
+    [
+        "*Usage*".to_string(),
+        format!("`{} list` shows the rules of this workspace", command_name),
+        format!(
+            "`{} add :emoji: owner/repo [@assignee...]` files messages reacted with :emoji: to owner/repo",
+            command_name
+        ),
+        format!("`{} remove :emoji:` removes the rule of :emoji:", command_name),
+        format!("`{} help` shows this message", command_name),
+    ]
+    .join("\n")
+}
+
+fn parse_rule_command(text: &str) -> Result<RuleCommand, String> {
+    let mut args = text.split_whitespace();
+    let subcommand = args.next().unwrap_or("help");
+    let args: Vec<&str> = args.collect();
+
+    match (subcommand, args.as_slice()) {
+        ("help", _) => Ok(RuleCommand::Help),
+        ("list", []) => Ok(RuleCommand::List),
+        ("add", [name, repo, assignees @ ..]) => {
+            if !is_repo(repo) {
+                return Err(format!("`{}` is not owner/repo", repo));
+            }
+
+            Ok(RuleCommand::Add {
+                name: parse_emoji(name)?,
+                repo: repo.to_string(),
+                assignees: assignees
+                    .iter()
+                    .map(|assignee| assignee.trim_start_matches('@').to_string())
+                    .collect(),
+            })
+        }
+        ("remove", [name]) => Ok(RuleCommand::Remove {
+            name: parse_emoji(name)?,
+        }),
+        _ => Err(format!("unknown command: `{}`", text.trim())),
+    }
+}
+
+// `:bug:` and `bug` both name the emoji
+fn parse_emoji(arg: &str) -> Result<String, String> {
+    let name = arg.trim_matches(':');
+    if name.is_empty() {
+        return Err(format!("`{}` is not an emoji", arg));
+    }
+    Ok(name.to_string())
+}
+
+pub fn is_repo(arg: &str) -> bool {
+    let parts: Vec<&str> = arg.split('/').collect();
+    parts.len() == 2 && parts.iter().all(|part| !part.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rule_command, RuleCommand};
+
+    #[test]
+    fn test_parse_rule_command() {
+        assert_eq!(parse_rule_command(""), Ok(RuleCommand::Help));
+        assert_eq!(parse_rule_command("help"), Ok(RuleCommand::Help));
+        assert_eq!(parse_rule_command(" list "), Ok(RuleCommand::List));
+        assert_eq!(
+            parse_rule_command("add :bug: uiur/bugs"),
+            Ok(RuleCommand::Add {
+                name: "bug".to_string(),
+                repo: "uiur/bugs".to_string(),
+                assignees: vec![],
+            })
+        );
+        assert_eq!(
+            parse_rule_command("add memo uiur/sandbox @uiur octocat"),
+            Ok(RuleCommand::Add {
+                name: "memo".to_string(),
+                repo: "uiur/sandbox".to_string(),
+                assignees: vec!["uiur".to_string(), "octocat".to_string()],
+            })
+        );
+        assert_eq!(
+            parse_rule_command("remove :bug:"),
+            Ok(RuleCommand::Remove {
+                name: "bug".to_string()
+            })
+        );
+
+        assert!(parse_rule_command("add :bug:").is_err());
+        assert!(parse_rule_command("add :bug: uiur").is_err());
+        assert!(parse_rule_command("add :: uiur/bugs").is_err());
+        assert!(parse_rule_command("remove").is_err());
+        assert!(parse_rule_command("list all").is_err());
+        assert!(parse_rule_command("delete :bug:").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use std::env;
+
+use actix_web::{
+    error::{ErrorInternalServerError, ErrorNotFound},
+    http::header,
+    web, HttpResponse, Responder,
+};
+use hmac::{Hmac, Mac};
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+
+use crate::{
+    entities,
+    slack::{SlackApi, SlackFile},
+};
+
+use super::webhook::slack_client_for_team;
+
+// GitHub caches images through its own proxy, so this mostly serves that proxy.
+const FILE_CACHE_MAX_AGE_SECS: u32 = 60 * 60 * 24;
+// Files are held in memory while they are served
+const FILE_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct FileQuery {
+    signature: String,
+}
+
+/// Url of the file proxy serving an image shared on Slack. Slack files are private to the
+/// workspace, so GitHub viewers can only see them through the proxy.
+pub fn file_proxy_url(team_id: i32, file_id: &str) -> String {
+    let http_host = env::var("E2D_HTTP_HOST").expect("E2D_HTTP_HOST is expected");
+    format!(
+        "{}/files/{}/{}?signature={}",
+        http_host,
+        team_id,
+        file_id,
+        hex::encode(mac(team_id, file_id).finalize().into_bytes())
+    )
+}
+
+/// Whether the file is served by the file proxy.
+pub fn is_proxied(file: &SlackFile) -> bool {
+    file.is_image() && file.size <= FILE_MAX_SIZE
+}
+
+// MASTER_KEY also signs API tokens, so proxy urls are signed with a key derived for them alone.
+fn signing_key() -> Vec<u8> {
+    let master_key = env::var("MASTER_KEY").expect("MASTER_KEY is expected");
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(master_key.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(b"emoji-to-do file proxy");
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Proxy urls end up in public issues, so each one only opens the file it was made for.
+fn mac(team_id: i32, file_id: &str) -> Hmac<sha2::Sha256> {
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&signing_key())
+        .expect("HMAC can take key of any size");
+    mac.update(format!("{}/{}", team_id, file_id).as_bytes());
+    mac
+}
+
+fn verify_signature(team_id: i32, file_id: &str, signature: &str) -> bool {
+    match hex::decode(signature) {
+        Ok(signature) => mac(team_id, file_id).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub async fn get_slack_file(
+    path: web::Path<(i32, String)>,
+    query: web::Query<FileQuery>,
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    slack_api: web::Data<dyn SlackApi>,
+) -> actix_web::Result<impl Responder> {
+    let (team_id, file_id) = path.into_inner();
+    if !verify_signature(team_id, &file_id, &query.signature) {
+        return Err(ErrorNotFound("file is not found"));
+    }
+
+    let team = entities::prelude::Team::find_by_id(team_id)
+        .one(connection.as_ref())
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("file is not found"))?;
+    let slack_client = slack_client_for_team(&slack_api, &team)?;
+
+    let file: SlackFile = slack_client.get_file_info(&file_id).await?;
+    // Anything else could be a page running scripts on our domain
+    if !is_proxied(&file) {
+        return Err(ErrorNotFound("file is not found"));
+    }
+
+    let content = slack_client
+        .download_file(&file.url_private, FILE_MAX_SIZE)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(file.mimetype)
+        .insert_header((
+            header::CACHE_CONTROL,
+            format!("max-age={}", FILE_CACHE_MAX_AGE_SECS),
+        ))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        // Opened directly, the file still can't run scripts or load anything
+        .insert_header((
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; sandbox",
+        ))
+        .body(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_proxy_url, verify_signature};
+
+    #[test]
+    fn test_file_proxy_url() {
+        std::env::set_var("MASTER_KEY", "master key for test");
+        std::env::set_var("E2D_HTTP_HOST", "https://api.emoji-to-do.com");
+
+        let url = file_proxy_url(1, "F1234");
+        let signature = url
+            .strip_prefix("https://api.emoji-to-do.com/files/1/F1234?signature=")
+            .unwrap();
+
+        assert!(verify_signature(1, "F1234", signature));
+        assert!(!verify_signature(2, "F1234", signature));
+        assert!(!verify_signature(1, "F5678", signature));
+        assert!(!verify_signature(1, "F1234", "not hex"));
+    }
+}
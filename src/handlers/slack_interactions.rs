@@ -0,0 +1,657 @@
+use std::sync::Arc;
+
+use actix_web::{
+    error::{ErrorBadRequest, ErrorInternalServerError},
+    web, HttpResponse, Responder,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
+    QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    entities,
+    github::{self, IssueTracker},
+    jobs::{self, Job},
+    slack::{
+        blocks::{
+            issue_card, issue_form, parse_issue_card_block_id, parse_issue_draft_block_id,
+            IssueCard, IssueForm, ASSIGN_TO_ME_ACTION_ID, CHANGE_REPO_ACTION_ID,
+            CLOSE_ISSUE_ACTION_ID, ISSUE_FORM_ASSIGNEES, ISSUE_FORM_BODY, ISSUE_FORM_CALLBACK_ID,
+            ISSUE_FORM_LABELS, ISSUE_FORM_REPO, ISSUE_FORM_TITLE, OPEN_ISSUE_FORM_ACTION_ID,
+        },
+        SlackApi, SlackInteraction, View,
+    },
+};
+
+use super::{
+    api::user_link::find_github_logins,
+    slack_commands::is_repo,
+    webhook::{
+        claim_message_filing, create_linked_issue, github_token_for_team, message_filing_conflict,
+        release_message_filing, slack_client_for_team, FiledMessage,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct InteractionRequestBody {
+    payload: String,
+}
+
+/// A click on an issue card, handled in the background.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IssueCardAction {
+    pub slack_team_id: String,
+    pub message_issue_id: i32,
+    pub action_id: String,
+    /// Selected option of a select menu
+    pub value: Option<String>,
+    pub user: String,
+    pub channel: String,
+    pub message_ts: String,
+    pub response_url: Option<String>,
+    pub is_ephemeral: bool,
+}
+
+/// A submitted issue form, filed in the background.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IssueFormSubmission {
+    pub slack_team_id: String,
+    pub issue_draft_id: i32,
+    pub title: String,
+    pub body: String,
+    pub repo: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+}
+
+// https://api.slack.com/interactivity/handling#payloads
+pub async fn create_slack_interactions(
+    form: web::Form<InteractionRequestBody>,
+    connection: web::Data<sea_orm::DatabaseConnection>,
+    slack_api: web::Data<dyn SlackApi>,
+) -> actix_web::Result<impl Responder> {
+    let interaction: SlackInteraction =
+        serde_json::from_str(&form.payload).map_err(ErrorBadRequest)?;
+    log::debug!("{:#?}", interaction);
+
+    match interaction {
+        SlackInteraction::BlockActions {
+            team,
+            user,
+            channel,
+            container,
+            response_url,
+            trigger_id,
+            actions,
+        } => {
+            for action in actions {
+                // Modals have to be opened within 3 seconds, so this can't wait for a job
+                if action.action_id == OPEN_ISSUE_FORM_ACTION_ID {
+                    if let Some(issue_draft_id) = parse_issue_draft_block_id(&action.block_id) {
+                        open_issue_form(
+                            connection.as_ref(),
+                            &slack_api,
+                            &team.id,
+                            issue_draft_id,
+                            trigger_id.as_deref(),
+                            response_url.as_deref(),
+                        )
+                        .await?;
+                    }
+                    continue;
+                }
+
+                let message_issue_id = match parse_issue_card_block_id(&action.block_id) {
+                    Some(message_issue_id) => message_issue_id,
+                    None => continue,
+                };
+                let (channel, message_ts) = match (&channel, &container.message_ts) {
+                    (Some(channel), Some(message_ts)) => (channel.id.clone(), message_ts.clone()),
+                    _ => continue,
+                };
+
+                let job = Job::IssueCardAction(IssueCardAction {
+                    slack_team_id: team.id.clone(),
+                    message_issue_id,
+                    action_id: action.action_id,
+                    value: action.selected_option.map(|option| option.value),
+                    user: user.id.clone(),
+                    channel,
+                    message_ts,
+                    response_url: response_url.clone(),
+                    is_ephemeral: container.is_ephemeral,
+                });
+                jobs::enqueue(connection.as_ref(), &team.id, &job)
+                    .await
+                    .map_err(ErrorInternalServerError)?;
+            }
+        }
+
+        SlackInteraction::ViewSubmission { team, view, .. }
+            if view.callback_id == ISSUE_FORM_CALLBACK_ID =>
+        {
+            return submit_issue_form(connection.as_ref(), &team.id, &view).await;
+        }
+
+        _ => {}
+    }
+
+    Ok(HttpResponse::Ok().body(""))
+}
+
+async fn open_issue_form(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    slack_team_id: &str,
+    issue_draft_id: i32,
+    trigger_id: Option<&str>,
+    response_url: Option<&str>,
+) -> actix_web::Result<()> {
+    let team = entities::prelude::Team::find()
+        .filter(entities::team::Column::SlackTeamId.eq(slack_team_id))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorBadRequest("team is not found"))?;
+    let slack_client = slack_client_for_team(slack_api, &team)?;
+
+    let issue_draft = team
+        .find_related(entities::prelude::IssueDraft)
+        .filter(entities::issue_draft::Column::Id.eq(issue_draft_id))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    match (issue_draft, trigger_id, response_url) {
+        (Some(issue_draft), Some(trigger_id), _) => {
+            // Kept to replace the prompt once the issue is filed
+            if let Some(response_url) = response_url {
+                let mut active_model = issue_draft.clone().into_active_model();
+                active_model.response_url = Set(Some(response_url.to_owned()));
+                active_model
+                    .update(connection)
+                    .await
+                    .map_err(ErrorInternalServerError)?;
+            }
+
+            let view = issue_form(&IssueForm {
+                issue_draft_id: issue_draft.id,
+                repo: &issue_draft.repo,
+                title: &issue_draft.title,
+                body: &issue_draft.body,
+                labels: &issue_draft.labels,
+                assignees: &issue_draft.assignees,
+            });
+            slack_client.open_view(trigger_id, &view).await
+        }
+        // The draft is gone once the issue is filed
+        (None, _, Some(response_url)) => {
+            slack_client
+                .replace_original(response_url, "This issue has already been filed", &[])
+                .await
+        }
+        _ => Ok(()),
+    }
+    .map_err(actix_web::Error::from)
+}
+
+// Errors are shown on the form. Otherwise the form closes and the issue is filed in the background.
+// https://api.slack.com/surfaces/modals/using#displaying_errors
+async fn submit_issue_form(
+    connection: &DatabaseConnection,
+    slack_team_id: &str,
+    view: &View,
+) -> actix_web::Result<HttpResponse> {
+    let issue_draft_id: i32 = view.private_metadata.parse().map_err(ErrorBadRequest)?;
+    let values = &view.state;
+
+    let repo = values.value(ISSUE_FORM_REPO, ISSUE_FORM_REPO).trim();
+    if !is_repo(repo) {
+        return Ok(HttpResponse::Ok().json(json!({
+            "response_action": "errors",
+            "errors": { ISSUE_FORM_REPO: "Enter a repository like owner/repo" },
+        })));
+    }
+
+    let job = Job::IssueFormSubmitted(IssueFormSubmission {
+        slack_team_id: slack_team_id.to_owned(),
+        issue_draft_id,
+        title: values.value(ISSUE_FORM_TITLE, ISSUE_FORM_TITLE).to_owned(),
+        body: values.value(ISSUE_FORM_BODY, ISSUE_FORM_BODY).to_owned(),
+        repo: repo.to_owned(),
+        labels: split_list(values.value(ISSUE_FORM_LABELS, ISSUE_FORM_LABELS)),
+        assignees: split_list(values.value(ISSUE_FORM_ASSIGNEES, ISSUE_FORM_ASSIGNEES))
+            .into_iter()
+            .map(|assignee| assignee.trim_start_matches('@').to_owned())
+            .collect(),
+    });
+    jobs::enqueue(connection, slack_team_id, &job)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().body(""))
+}
+
+// Labels may contain spaces, so only commas separate items.
+fn split_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+pub async fn handle_issue_form_submission(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    submission: IssueFormSubmission,
+) -> actix_web::Result<()> {
+    let (issue_draft, team) =
+        match entities::prelude::IssueDraft::find_by_id(submission.issue_draft_id)
+            .find_also_related(entities::prelude::Team)
+            .one(connection)
+            .await
+            .map_err(ErrorInternalServerError)?
+        {
+            Some(found) => found,
+            // The form was submitted again after the draft was filed
+            None => {
+                log::info!("issue draft {} is already filed", submission.issue_draft_id);
+                return Ok(());
+            }
+        };
+    let team = team
+        .filter(|team| team.slack_team_id == submission.slack_team_id)
+        .ok_or_else(|| ErrorInternalServerError("team is not found"))?;
+
+    if !claim_message_filing(connection, team.id, &issue_draft.channel, &issue_draft.ts)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        return Err(message_filing_conflict());
+    }
+    let result = file_issue_draft(
+        connection,
+        slack_api,
+        issue_tracker,
+        &team,
+        &issue_draft,
+        submission,
+    )
+    .await;
+    release_message_filing(connection, team.id, &issue_draft.channel, &issue_draft.ts).await;
+    result
+}
+
+async fn file_issue_draft(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    issue_draft: &entities::issue_draft::Model,
+    submission: IssueFormSubmission,
+) -> actix_web::Result<()> {
+    let slack_client = slack_client_for_team(slack_api, team)?;
+
+    // A retry after the issue was filed, or a reaction filed the message in the meantime
+    let message_issue = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::TeamId.eq(team.id))
+        .filter(entities::message_issue::Column::Channel.eq(issue_draft.channel.as_str()))
+        .filter(entities::message_issue::Column::Ts.eq(issue_draft.ts.as_str()))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let html_url = match message_issue {
+        Some(message_issue) => message_issue.html_url,
+        None => {
+            let github_token = github_token_for_team(issue_tracker, team).await?;
+            let message = FiledMessage {
+                channel: &issue_draft.channel,
+                ts: &issue_draft.ts,
+                thread_ts: &issue_draft.thread_ts,
+                user: &issue_draft.user,
+                reply_mode: issue_draft.reply_mode.parse().unwrap_or_default(),
+            };
+            // Milestones are numbered per repository
+            let milestone = issue_draft
+                .milestone
+                .filter(|_| submission.repo.eq_ignore_ascii_case(&issue_draft.repo));
+            create_linked_issue(
+                connection,
+                issue_tracker,
+                team,
+                &slack_client,
+                &github_token,
+                &message,
+                &submission.repo,
+                &github::NewIssue {
+                    title: submission.title,
+                    body: submission.body,
+                    assignees: submission.assignees,
+                    labels: submission.labels,
+                    milestone,
+                    issue_type: issue_draft.issue_type.clone(),
+                },
+            )
+            .await?
+            .html_url
+        }
+    };
+
+    // The issue is filed, so the job must not fail from here
+    if let Err(e) = entities::prelude::IssueDraft::delete_by_id(issue_draft.id)
+        .exec(connection)
+        .await
+    {
+        log::error!("failed to delete issue draft {}: {}", issue_draft.id, e);
+    }
+    // The prompt would offer to file the message again
+    if let Some(response_url) = &issue_draft.response_url {
+        let text = format!("Filed {}", html_url);
+        if let Err(e) = slack_client
+            .replace_original(response_url, &text, &[])
+            .await
+        {
+            log::error!("failed to replace the prompt of {}: {}", html_url, e);
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_issue_card_action(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    action: IssueCardAction,
+) -> actix_web::Result<()> {
+    let (message_issue, team) =
+        entities::prelude::MessageIssue::find_by_id(action.message_issue_id)
+            .find_also_related(entities::prelude::Team)
+            .one(connection)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .ok_or_else(|| ErrorInternalServerError("message issue is not found"))?;
+    let team = team
+        .filter(|team| team.slack_team_id == action.slack_team_id)
+        .ok_or_else(|| ErrorInternalServerError("team is not found"))?;
+
+    let slack_client = slack_client_for_team(slack_api, &team)?;
+    let github_token = github_token_for_team(issue_tracker, &team).await?;
+
+    let (message_issue, issue) = match action.action_id.as_str() {
+        ASSIGN_TO_ME_ACTION_ID => {
+            let github_login = find_github_logins(connection, team.id, &[action.user.as_str()])
+                .await
+                .map_err(ErrorInternalServerError)?
+                .remove(&action.user);
+            // Slack names may belong to someone else on GitHub, so only linked accounts are assigned
+            let github_login = match github_login {
+                Some(github_login) => github_login,
+                None => {
+                    slack_client
+                        .post_ephemeral(
+                            &action.channel,
+                            &action.user,
+                            "Link your GitHub account on the dashboard to be assigned to issues.",
+                            &[],
+                            None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+            let logins = vec![github_login];
+            let issue = issue_tracker
+                .add_assignees(
+                    &github_token,
+                    &message_issue.repo,
+                    message_issue.issue_number,
+                    &logins,
+                )
+                .await?;
+
+            if !issue.unassigned_logins(&logins).is_empty() {
+                slack_client
+                    .post_ephemeral(
+                        &action.channel,
+                        &action.user,
+                        &format!("failed to assign @{} on GitHub", logins[0]),
+                        &[],
+                        None,
+                    )
+                    .await?;
+            }
+            (message_issue, issue)
+        }
+
+        CLOSE_ISSUE_ACTION_ID => {
+            let issue = issue_tracker
+                .update_issue(
+                    &github_token,
+                    &message_issue.repo,
+                    message_issue.issue_number,
+                    &json!({ "state": "closed" }),
+                )
+                .await?;
+            (message_issue, issue)
+        }
+
+        CHANGE_REPO_ACTION_ID => {
+            // Only repositories of the team's rules can be chosen. Another choice fails the same
+            // way on every attempt, so the user is told instead of the job being retried.
+            let team_repos = team_repos(connection, &team).await?;
+            let repo = match action.value.as_deref() {
+                Some(repo) if team_repos.iter().any(|r| r == repo) => repo,
+                value => {
+                    log::warn!("repo is not found: {:?}", value);
+                    slack_client
+                        .post_ephemeral(
+                            &action.channel,
+                            &action.user,
+                            "The issue can only be moved to a repository of this workspace's rules.",
+                            &[],
+                            None,
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+
+            move_issue(
+                connection,
+                issue_tracker,
+                &github_token,
+                message_issue,
+                repo,
+            )
+            .await?
+        }
+
+        _ => {
+            log::warn!("unknown action: {}", action.action_id);
+            return Ok(());
+        }
+    };
+
+    let other_repos = other_repos(connection, &team, &message_issue.repo).await?;
+    let blocks = issue_card_blocks(
+        Some(message_issue.id),
+        &message_issue.repo,
+        &issue,
+        &other_repos,
+    );
+
+    match (&action.response_url, action.is_ephemeral) {
+        (Some(response_url), true) => {
+            slack_client
+                .replace_original(response_url, &issue.html_url, &blocks)
+                .await
+        }
+        _ => {
+            slack_client
+                .update_message(
+                    &action.channel,
+                    &action.message_ts,
+                    &issue.html_url,
+                    &blocks,
+                )
+                .await
+        }
+    }?;
+
+    Ok(())
+}
+
+// Issues can only be transferred through the GraphQL API, so a copy is filed instead and the
+// original is closed with a pointer to it. The copy is recorded as soon as it is filed, so a retry
+// only finishes closing the original instead of filing another copy.
+async fn move_issue(
+    connection: &DatabaseConnection,
+    issue_tracker: &dyn IssueTracker,
+    github_token: &str,
+    message_issue: entities::message_issue::Model,
+    repo: &str,
+) -> actix_web::Result<(entities::message_issue::Model, github::Issue)> {
+    let issue = issue_tracker
+        .get_issue(
+            github_token,
+            &message_issue.repo,
+            message_issue.issue_number,
+        )
+        .await?;
+    let (message_issue, new_issue) = if message_issue.repo.eq_ignore_ascii_case(repo) {
+        (message_issue, issue)
+    } else {
+        let new_issue = issue_tracker
+            .create_issue(
+                github_token,
+                repo,
+                &github::NewIssue {
+                    title: issue.title.clone(),
+                    body: issue.body.clone().unwrap_or_default(),
+                    assignees: issue.assignee_logins(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let moved_from_repo = message_issue.repo.clone();
+        let moved_from_issue_number = message_issue.issue_number;
+        let mut active_model = message_issue.into_active_model();
+        active_model.repo = Set(repo.to_owned());
+        active_model.issue_number = Set(new_issue.number);
+        active_model.html_url = Set(new_issue.html_url.clone());
+        active_model.moved_from_repo = Set(Some(moved_from_repo));
+        active_model.moved_from_issue_number = Set(Some(moved_from_issue_number));
+        let message_issue = active_model
+            .update(connection)
+            .await
+            .map_err(ErrorInternalServerError)?;
+        (message_issue, new_issue)
+    };
+
+    let (moved_from_repo, moved_from_issue_number) = match (
+        &message_issue.moved_from_repo,
+        message_issue.moved_from_issue_number,
+    ) {
+        (Some(repo), Some(number)) => (repo.clone(), number),
+        _ => return Ok((message_issue, new_issue)),
+    };
+    // Closing is a no-op the second time, so the comment comes last and is posted once
+    issue_tracker
+        .update_issue(
+            github_token,
+            &moved_from_repo,
+            moved_from_issue_number,
+            &json!({ "state": "closed" }),
+        )
+        .await?;
+    issue_tracker
+        .create_comment(
+            github_token,
+            &moved_from_repo,
+            moved_from_issue_number,
+            &format!("Moved to {}", new_issue.html_url),
+        )
+        .await?;
+
+    let mut active_model = message_issue.into_active_model();
+    active_model.moved_from_repo = Set(None);
+    active_model.moved_from_issue_number = Set(None);
+    let message_issue = active_model
+        .update(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
+
+    Ok((message_issue, new_issue))
+}
+
+pub fn issue_card_blocks(
+    message_issue_id: Option<i32>,
+    repo: &str,
+    issue: &github::Issue,
+    other_repos: &[String],
+) -> Vec<serde_json::Value> {
+    let labels = issue.label_names();
+    let assignees = issue.assignee_logins();
+
+    issue_card(&IssueCard {
+        message_issue_id,
+        repo,
+        number: issue.number,
+        title: &issue.title,
+        html_url: &issue.html_url,
+        closed: issue.is_closed(),
+        labels: &labels,
+        assignees: &assignees,
+        other_repos,
+    })
+}
+
+async fn team_repos(
+    connection: &DatabaseConnection,
+    team: &entities::team::Model,
+) -> actix_web::Result<Vec<String>> {
+    let mut repos: Vec<String> = team
+        .find_related(entities::prelude::Reaction)
+        .all(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .into_iter()
+        .map(|reaction| reaction.repo)
+        .collect();
+    repos.sort();
+    repos.dedup();
+
+    Ok(repos)
+}
+
+/// Repositories of the team's rules, other than `repo`.
+pub async fn other_repos(
+    connection: &DatabaseConnection,
+    team: &entities::team::Model,
+    repo: &str,
+) -> actix_web::Result<Vec<String>> {
+    let mut repos = team_repos(connection, team).await?;
+    repos.retain(|r| !r.eq_ignore_ascii_case(repo));
+
+    Ok(repos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_list;
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(
+            split_list("bug, good first issue,,ci "),
+            vec!["bug", "good first issue", "ci"]
+        );
+        assert!(split_list(" ").is_empty());
+    }
+}
@@ -1,141 +1,932 @@
-use std::collections::HashMap;
+use std::sync::Arc;
 
-use actix_web::{error::ErrorInternalServerError, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{
+    error::{ErrorConflict, ErrorInternalServerError},
+    web, HttpResponse, Responder,
+};
+use chrono::{TimeZone, Utc};
 use futures::{future::try_join_all, TryFutureExt};
 
-use regex::{Captures, Regex};
+use regex::Regex;
 
-use sea_orm::*;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend,
+    DatabaseConnection, DbErr, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder,
+    Set, Statement,
+};
 
+use super::{
+    api::{reaction::find_reactions, user_link::find_github_logins},
+    slack_commands::{format_rules, is_repo},
+    slack_files::{file_proxy_url, is_proxied},
+    slack_interactions::{issue_card_blocks, other_repos},
+};
 use crate::{
-    entities, github,
-    slack::{self, SlackEvent, SlackItem, SlackRequest},
+    clock::unix_now,
+    entities,
+    github::{self, GithubClientError, IssueTracker, NewIssue},
+    issue_template::{self, FileContext, IssueContext, MessageContext, RenderedIssue},
+    jobs::{self, Job},
+    mrkdwn::{self, Mode, Names},
+    secret,
+    slack::{
+        blocks, directory, escape, ReplyMode, SlackApi, SlackClient, SlackClientError, SlackEvent,
+        SlackFile, SlackItem, SlackMessage, SlackRequest,
+    },
 };
 
+// An unfinished event is treated as still in progress for this long. Retries arriving later are
+// processed again, since the original attempt most likely died.
+const SLACK_EVENT_LEASE_SECS: i64 = 60;
+// Slack gives up retrying within an hour. Event ids are kept for a day, well past that window.
+const SLACK_EVENT_RETENTION_SECS: i64 = 60 * 60 * 24;
+// A message held this long is assumed to have been left by a job that died while filing it.
+const MESSAGE_FILING_LEASE_SECS: i64 = 5 * 60;
+// Drafts nobody submitted within a week are forgotten.
+const ISSUE_DRAFT_RETENTION_SECS: i64 = 60 * 60 * 24 * 7;
+
 pub async fn create_slack_events(
     data: web::Json<SlackRequest>,
-    req: HttpRequest,
     connection: web::Data<sea_orm::DatabaseConnection>,
 ) -> actix_web::Result<impl Responder> {
-    // Ignore duplicated requests due to http timeout
-    if let Some(header_value) = req.headers().get("X-Slack-Retry-Reason") {
-        if header_value.to_str().unwrap_or_default() == "http_timeout" {
-            return Ok(HttpResponse::Ok().body(""));
-        }
-    }
-
     log::debug!("{:#?}", data);
 
     match data.0 {
         SlackRequest::UrlVerification { challenge } => Ok(HttpResponse::Ok().body(challenge)),
 
-        SlackRequest::EventCallback { event } => match event {
-            SlackEvent::ReactionAdded {
+        SlackRequest::EventCallback {
+            event_id,
+            team_id,
+            event_time,
+            event,
+        } => {
+            let claimed = claim_slack_event(connection.as_ref(), &event_id, &team_id, event_time)
+                .await
+                .map_err(ErrorInternalServerError)?;
+            if !claimed {
+                log::info!("skip duplicated slack event: {}", event_id);
+                return Ok(HttpResponse::Ok().body(""));
+            }
+
+            let result = enqueue_slack_event(connection.as_ref(), &team_id, event).await;
+
+            if result.is_ok() {
+                finish_slack_event(connection.as_ref(), &event_id).await
+            } else {
+                release_slack_event(connection.as_ref(), &event_id).await
+            }
+            .map_err(ErrorInternalServerError)?;
+
+            result.map_err(ErrorInternalServerError)?;
+            Ok(HttpResponse::Ok().body(""))
+        }
+
+        _ => Err(actix_web::error::ErrorBadRequest("")),
+    }
+}
+
+// Slack expects a response within 3 seconds, so events are handled by background jobs.
+async fn enqueue_slack_event(
+    connection: &DatabaseConnection,
+    team_id: &str,
+    event: SlackEvent,
+) -> Result<(), DbErr> {
+    let job = match event {
+        SlackEvent::ReactionAdded {
+            user,
+            reaction,
+            item,
+        } => entities::prelude::Reaction::find_by_slack_team_id_and_name(team_id, &reaction)
+            .one(connection)
+            .await?
+            .map(|reaction_record| Job::ReactionAdded {
+                reaction_id: reaction_record.id,
                 user,
-                reaction,
                 item,
-            } => handle_reaction_added(user, reaction, item, connection).await,
+            }),
 
-            SlackEvent::AppMention {
-                user,
-                channel,
-                text,
-            } => handle_app_mention(user, channel, text).await,
+        SlackEvent::AppMention {
+            user,
+            channel,
+            text,
+            ts,
+            thread_ts,
+        } => Some(Job::AppMention {
+            team_id: team_id.to_owned(),
+            user,
+            channel,
+            text,
+            ts,
+            thread_ts,
+        }),
 
-            _ => Ok(HttpResponse::Ok().body("")),
-        },
+        // The cached profile would show the old name until it expires
+        SlackEvent::UserChange { user } => {
+            directory::invalidate_user(team_id, &user.id);
+            None
+        }
 
-        _ => Err(actix_web::error::ErrorBadRequest("")),
+        _ => None,
+    };
+
+    if let Some(job) = job {
+        jobs::enqueue(connection, team_id, &job).await?;
     }
+
+    Ok(())
 }
 
-async fn handle_reaction_added(
-    user: String,
-    _reaction: String,
-    item: SlackItem,
-    connection: web::Data<sea_orm::DatabaseConnection>,
-) -> actix_web::Result<HttpResponse> {
-    let reactioner = slack::get_user_info(&user).await?;
-    let team_id = reactioner.team_id;
+// Records the event as being processed. Returns false when it has already been processed, or is
+// still being processed by an earlier delivery of the same event.
+async fn claim_slack_event(
+    connection: &DatabaseConnection,
+    event_id: &str,
+    slack_team_id: &str,
+    event_time: i64,
+) -> Result<bool, DbErr> {
+    let now = unix_now();
 
-    let team = entities::prelude::Team::find()
-        .filter(entities::team::Column::SlackTeamId.eq(team_id.as_str()))
-        .one(connection.as_ref())
+    entities::prelude::SlackEvent::delete_many()
+        .filter(entities::slack_event::Column::EventTime.lt(now - SLACK_EVENT_RETENTION_SECS))
+        .exec(connection)
+        .await?;
+
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            r#"
+            insert into slack_events (event_id, slack_team_id, event_time, started_at)
+            values (?, ?, ?, ?)
+            on conflict (event_id) do update set started_at = excluded.started_at
+            where slack_events.finished_at is null and slack_events.started_at <= ?
+            "#,
+            vec![
+                event_id.into(),
+                slack_team_id.into(),
+                event_time.into(),
+                now.into(),
+                (now - SLACK_EVENT_LEASE_SECS).into(),
+            ],
+        ))
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn finish_slack_event(connection: &DatabaseConnection, event_id: &str) -> Result<(), DbErr> {
+    entities::prelude::SlackEvent::update_many()
+        .col_expr(
+            entities::slack_event::Column::FinishedAt,
+            Expr::value(unix_now()),
+        )
+        .filter(entities::slack_event::Column::EventId.eq(event_id))
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+// Forgets a failed attempt so that Slack's retry of the event gets processed.
+async fn release_slack_event(connection: &DatabaseConnection, event_id: &str) -> Result<(), DbErr> {
+    entities::prelude::SlackEvent::delete_many()
+        .filter(entities::slack_event::Column::EventId.eq(event_id))
+        .filter(entities::slack_event::Column::FinishedAt.is_null())
+        .exec(connection)
+        .await?;
+
+    Ok(())
+}
+
+/// Holds the message while it is being filed, so that concurrent jobs don't file it twice.
+/// Returns false when another job holds it.
+pub async fn claim_message_filing(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    channel: &str,
+    ts: &str,
+) -> Result<bool, DbErr> {
+    let now = unix_now();
+    let result = connection
+        .execute(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            r#"
+            insert into message_filings (team_id, channel, ts, started_at)
+            values (?, ?, ?, ?)
+            on conflict (team_id, channel, ts) do update set started_at = excluded.started_at
+            where message_filings.started_at <= ?
+            "#,
+            vec![
+                team_id.into(),
+                channel.into(),
+                ts.into(),
+                now.into(),
+                (now - MESSAGE_FILING_LEASE_SECS).into(),
+            ],
+        ))
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn release_message_filing(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    channel: &str,
+    ts: &str,
+) {
+    if let Err(e) = entities::prelude::MessageFiling::delete_many()
+        .filter(entities::message_filing::Column::TeamId.eq(team_id))
+        .filter(entities::message_filing::Column::Channel.eq(channel))
+        .filter(entities::message_filing::Column::Ts.eq(ts))
+        .exec(connection)
         .await
-        .map_err(ErrorInternalServerError)?
-        .unwrap();
+    {
+        log::error!("failed to release {} in {}: {}", ts, channel, e);
+    }
+}
+
+/// The error of a job finding the message held by another. The retry sees the other's issue.
+pub fn message_filing_conflict() -> actix_web::Error {
+    ErrorConflict("the message is being filed by another job")
+}
+
+pub fn slack_client_for_team(
+    slack_api: &Arc<dyn SlackApi>,
+    team: &entities::team::Model,
+) -> actix_web::Result<SlackClient> {
+    let encrypted_token = team
+        .slack_bot_token
+        .as_deref()
+        .ok_or_else(|| ErrorInternalServerError("slack app is not installed to the team"))?;
+    let token = secret::decrypt(encrypted_token).map_err(ErrorInternalServerError)?;
+
+    Ok(SlackClient::new(
+        team.slack_team_id.clone(),
+        token,
+        slack_api.clone(),
+    ))
+}
 
-    // let team = Team::find(&connection, &team_id)
-    //     .await
-    //     .map_err(actix_web::error::ErrorInternalServerError)?
-    //     .ok_or(actix_web::error::ErrorNotFound("team is not found"))?;
+pub async fn github_token_for_team(
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+) -> actix_web::Result<String> {
+    let installation_id = team
+        .github_installation_id
+        .ok_or_else(|| ErrorInternalServerError("github app is not installed"))?;
+    let token = issue_tracker.installation_token(installation_id).await?;
 
-    let record: Option<_> = entities::prelude::Reaction::find()
-        .filter(entities::team::Column::SlackTeamId.eq(team.id))
-        .one(connection.as_ref())
+    Ok(token)
+}
+
+/// Explains a failed Slack or GitHub call to the user who triggered it. Other errors are ours,
+/// and only logged.
+pub fn client_error_message(e: &actix_web::Error) -> Option<String> {
+    e.as_error::<SlackClientError>()
+        .map(SlackClientError::user_message)
+        .or_else(|| {
+            e.as_error::<GithubClientError>()
+                .map(GithubClientError::user_message)
+        })
+}
+
+/// The response body of a failed Slack or GitHub call, for debugging.
+pub fn client_error_body(e: &actix_web::Error) -> Option<&str> {
+    e.as_error::<SlackClientError>()
+        .and_then(SlackClientError::body)
+        .or_else(|| {
+            e.as_error::<GithubClientError>()
+                .and_then(GithubClientError::body)
+        })
+}
+
+/// Whether the call that failed with `e` may succeed when made again. A missing scope or
+/// repository won't be fixed by retrying.
+pub fn is_retryable(e: &actix_web::Error) -> bool {
+    if let Some(e) = e.as_error::<SlackClientError>() {
+        return e.is_retryable();
+    }
+    if let Some(e) = e.as_error::<GithubClientError>() {
+        return e.is_retryable();
+    }
+    true
+}
+
+pub async fn handle_reaction_added(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    reaction_id: i32,
+    user: String,
+    item: SlackItem,
+) -> actix_web::Result<()> {
+    let record: Option<_> = entities::prelude::Reaction::find_by_id(reaction_id)
+        .one(connection)
         .await
         .map_err(ErrorInternalServerError)?;
 
     if let Some(reaction_record) = record {
         log::info!("{:#?}", reaction_record);
+        let team = entities::prelude::Team::find_by_id(reaction_record.team_id)
+            .one(connection)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .ok_or_else(|| ErrorInternalServerError("team is not found"))?;
+
         if let SlackItem::Message { channel, ts } = item {
-            let messages = slack::get_messages(&channel, &ts, 3).await.map_err(|_| {
-                actix_web::error::ErrorInternalServerError("failed to fetch slack messages")
-            })?;
+            let rule = IssueRule::load(connection, reaction_record).await?;
+            let result = file_issue(
+                connection,
+                slack_api,
+                issue_tracker,
+                &team,
+                &rule,
+                &user,
+                channel,
+                ts,
+            )
+            .await;
+            // `file_issue` fails only before the issue is filed, so the user isn't told of a
+            // failure that didn't happen
+            if let Err(e) = &result {
+                // Retried errors would notify on every attempt
+                if let Some(message) = client_error_message(e).filter(|_| !is_retryable(e)) {
+                    notify_failure(slack_api, &team, &user, &rule.repo, &message).await;
+                }
+            }
+            result?;
+        }
+    }
+    Ok(())
+}
 
-            let permalink = slack::get_permalink(&channel, &ts)
-                .await
-                .unwrap_or("".to_string());
+// Sent as a direct message, since the app may not be able to post in the channel.
+async fn notify_failure(
+    slack_api: &Arc<dyn SlackApi>,
+    team: &entities::team::Model,
+    user: &str,
+    repo: &str,
+    message: &str,
+) {
+    let text = format!(
+        "Failed to file the message to {}. {}",
+        escape(repo),
+        message
+    );
+    let result = match slack_client_for_team(slack_api, team) {
+        Ok(slack_client) => slack_client
+            .post_message(user, &text, &[], None)
+            .await
+            .map_err(actix_web::Error::from),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::error!("failed to notify {} of the failure: {}", user, e);
+    }
+}
+
+/// How an issue is filed. Reactions follow their rule, while mentions file with the defaults.
+pub struct IssueRule {
+    repo: String,
+    include_thread: bool,
+    title_template: Option<String>,
+    body_template: Option<String>,
+    milestone: Option<i32>,
+    issue_type: Option<String>,
+    reply_mode: Option<String>,
+    confirm: bool,
+    assignees: Vec<String>,
+    labels: Vec<String>,
+}
+
+impl IssueRule {
+    async fn load(
+        connection: &DatabaseConnection,
+        reaction: entities::reaction::Model,
+    ) -> actix_web::Result<Self> {
+        let assignees = reaction
+            .find_related(entities::prelude::ReactionAssignee)
+            .all(connection)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .into_iter()
+            .map(|reaction_assignee| reaction_assignee.name)
+            .collect();
+        let labels = reaction
+            .find_related(entities::prelude::ReactionLabel)
+            .all(connection)
+            .await
+            .map_err(ErrorInternalServerError)?
+            .into_iter()
+            .map(|reaction_label| reaction_label.name)
+            .collect();
+
+        Ok(IssueRule {
+            repo: reaction.repo,
+            include_thread: reaction.include_thread,
+            title_template: reaction.title_template,
+            body_template: reaction.body_template,
+            milestone: reaction.milestone,
+            issue_type: reaction.issue_type,
+            reply_mode: reaction.reply_mode,
+            confirm: reaction.confirm,
+            assignees,
+            labels,
+        })
+    }
+
+    fn for_repo(repo: &str) -> Self {
+        IssueRule {
+            repo: repo.to_owned(),
+            include_thread: true,
+            title_template: None,
+            body_template: None,
+            milestone: None,
+            issue_type: None,
+            reply_mode: None,
+            confirm: false,
+            assignees: vec![],
+            labels: vec![],
+        }
+    }
+}
+
+// Files the message at `ts` as an issue on behalf of `user`, or comments on the issue the message
+// is already linked to.
+#[allow(clippy::too_many_arguments)]
+async fn file_issue(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    rule: &IssueRule,
+    user: &str,
+    channel: String,
+    ts: String,
+) -> actix_web::Result<()> {
+    if !claim_message_filing(connection, team.id, &channel, &ts)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        return Err(message_filing_conflict());
+    }
+
+    let result = file_claimed_issue(
+        connection,
+        slack_api,
+        issue_tracker,
+        team,
+        rule,
+        user,
+        channel.clone(),
+        ts.clone(),
+    )
+    .await;
+    release_message_filing(connection, team.id, &channel, &ts).await;
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn file_claimed_issue(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    rule: &IssueRule,
+    user: &str,
+    channel: String,
+    ts: String,
+) -> actix_web::Result<()> {
+    let slack_client = slack_client_for_team(slack_api, team)?;
+
+    let reactioner = slack_client.get_user_info(user).await?;
+
+    let messages = get_context_messages(&slack_client, &channel, &ts, rule.include_thread).await?;
+
+    // Replies go to the thread the reacted message belongs to
+    let thread_ts = messages
+        .iter()
+        .find(|message| message.ts == ts)
+        .and_then(|message| message.thread_ts.clone())
+        .unwrap_or_else(|| ts.clone());
+    let reply_mode = reply_mode(team, rule);
+
+    let permalink = slack_client
+        .get_permalink(&channel, &ts)
+        .await
+        .unwrap_or("".to_string());
+
+    let users = try_join_all(
+        messages
+            .iter()
+            .map(|message| slack_client.get_user_info(&message.user)),
+    )
+    .await?;
+
+    let mut names = Names::default();
+    for user in &users {
+        names.users.insert(user.id.clone(), user.name.clone());
+    }
+    let mentions = mrkdwn::mentions(messages.iter().map(|message| message.text.as_str()));
+    slack_client.resolve_names(&mentions, &mut names).await;
+
+    let mut slack_user_ids: Vec<&str> = vec![user];
+    slack_user_ids.extend(messages.iter().map(|message| message.user.as_str()));
+    slack_user_ids.extend(mentions.users.iter().map(String::as_str));
+    names.github_logins = find_github_logins(connection, team.id, &slack_user_ids)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let reactioner_login = names.github_logins.get(user).cloned();
 
-            let users = try_join_all(
-                messages
+    let message_contexts: Vec<MessageContext> = messages
+        .iter()
+        .map(|message| {
+            let empty_username = "";
+            let username = users
+                .iter()
+                .find(|user| user.id == message.user)
+                .map(|user| user.name.as_str())
+                .unwrap_or(empty_username);
+            MessageContext {
+                author: username.to_string(),
+                author_login: names.github_logins.get(&message.user).cloned(),
+                text: mrkdwn::convert(&message.text, Mode::Body, &names),
+                title_text: mrkdwn::convert(&message.text, Mode::Title, &names),
+                ts: message.ts.clone(),
+                files: message
+                    .files
                     .iter()
-                    .map(|message| slack::get_user_info(&message.user)),
+                    .map(|file| file_context(team.id, file))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut authors: Vec<String> = vec![];
+    for message in &message_contexts {
+        if !authors.contains(&message.author) {
+            authors.push(message.author.clone());
+        }
+    }
+
+    let files = message_contexts
+        .iter()
+        .flat_map(|message| message.files.clone())
+        .collect();
+
+    let message = message_contexts
+        .iter()
+        .find(|m| m.ts == ts)
+        .or_else(|| message_contexts.last())
+        .cloned()
+        .ok_or_else(|| ErrorInternalServerError("reacted message is not found"))?;
+
+    let context = IssueContext {
+        message,
+        messages: message_contexts,
+        authors,
+        files,
+        channel: channel.clone(),
+        permalink,
+        reactioner: reactioner.name.clone(),
+        reactioner_login: reactioner_login.clone(),
+        timestamp: format_slack_ts(&ts),
+    };
+
+    let github_token = github_token_for_team(issue_tracker, team).await?;
+
+    // The message already has an issue, so add to it instead of filing a duplicate
+    let message_issue = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::TeamId.eq(team.id))
+        .filter(entities::message_issue::Column::Channel.eq(channel.as_str()))
+        .filter(entities::message_issue::Column::Ts.eq(ts.as_str()))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    if let Some(message_issue) = message_issue {
+        let comment = issue_tracker
+            .create_comment(
+                &github_token,
+                &message_issue.repo,
+                message_issue.issue_number,
+                &repeat_reaction_comment(&context),
             )
             .await?;
 
-            let mut slack_user_map = HashMap::new();
-            for user in &users {
-                slack_user_map.insert(user.id.clone(), user.name.clone());
-            }
+        // The comment exists at this point. Failing the job would comment again on retry.
+        if let Err(e) = slack_client
+            .reply(
+                reply_mode,
+                &channel,
+                &thread_ts,
+                user,
+                &format!("<@{}> {}", user, comment.html_url),
+                &[],
+            )
+            .await
+        {
+            log::error!("failed to reply {}: {}", comment.html_url, e);
+        }
+        return Ok(());
+    }
 
-            let text = messages
-                .iter()
-                .map(|message| {
-                    let empty_username = "";
-                    let username = users
-                        .iter()
-                        .find(|user| user.id == message.user)
-                        .map(|user| user.name.as_str())
-                        .unwrap_or(empty_username);
-                    format!(
-                        "{}: {}",
-                        username,
-                        humanize_slack_formatted_text(&message.text, &slack_user_map)
-                    )
-                })
-                .collect::<Vec<String>>()
-                .join("\n");
+    // Templates saved before they were checked strictly may not render. The message is still
+    // filed, with the default templates.
+    let RenderedIssue { title, body } = issue_template::render(
+        rule.title_template.as_deref(),
+        rule.body_template.as_deref(),
+        &context,
+    )
+    .or_else(|e| {
+        log::error!("failed to render the templates of {}: {}", rule.repo, e);
+        issue_template::render(None, None, &context)
+    })
+    .map_err(ErrorInternalServerError)?;
 
-            let title: String = messages
-                .first()
-                .map(|m| String::from(&m.text))
-                .unwrap_or_default();
+    // Without assignees in the rule, the issue goes to whoever asked for it
+    let assignees = if rule.assignees.is_empty() {
+        reactioner_login.into_iter().collect()
+    } else {
+        rule.assignees.clone()
+    };
 
-            let title = humanize_slack_formatted_text(&title, &slack_user_map);
+    // The reactioner edits the issue in a modal before it is filed
+    if rule.confirm {
+        // `created_at` is stored in SQLite's datetime format
+        let expired_at = Utc.timestamp(unix_now() - ISSUE_DRAFT_RETENTION_SECS, 0);
+        entities::prelude::IssueDraft::delete_many()
+            .filter(
+                entities::issue_draft::Column::CreatedAt
+                    .lt(expired_at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            )
+            .exec(connection)
+            .await
+            .map_err(ErrorInternalServerError)?;
 
-            let body = format!("```\n{}\n```\n{}", &text, permalink);
-            let issue = github::create_issue(&reaction_record.repo, &title, &body).await?;
+        let issue_draft = entities::issue_draft::ActiveModel {
+            team_id: Set(team.id),
+            user: Set(user.to_owned()),
+            channel: Set(channel.clone()),
+            ts: Set(ts.clone()),
+            thread_ts: Set(thread_ts.clone()),
+            reply_mode: Set(reply_mode.as_str().to_owned()),
+            repo: Set(rule.repo.clone()),
+            title: Set(title),
+            body: Set(body),
+            labels: Set(rule.labels.join(", ")),
+            assignees: Set(assignees.join(", ")),
+            milestone: Set(rule.milestone),
+            issue_type: Set(rule.issue_type.clone()),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
 
-            slack::post_message(
+        let blocks =
+            blocks::issue_draft_prompt(issue_draft.id, &issue_draft.repo, &issue_draft.title);
+        slack_client
+            .post_ephemeral(
                 &channel,
-                &format!("<@{}> {}", reactioner.name, issue.html_url),
+                user,
+                &format!("File this message to {}?", rule.repo),
+                &blocks,
+                Some(&thread_ts),
             )
-            .await
-            .map_err(|_| actix_web::error::ErrorInternalServerError(""))?;
+            .await?;
+        return Ok(());
+    }
+
+    let message = FiledMessage {
+        channel: &channel,
+        ts: &ts,
+        thread_ts: &thread_ts,
+        user,
+        reply_mode,
+    };
+    create_linked_issue(
+        connection,
+        issue_tracker,
+        team,
+        &slack_client,
+        &github_token,
+        &message,
+        &rule.repo,
+        &NewIssue {
+            title,
+            body,
+            assignees,
+            labels: rule.labels.clone(),
+            milestone: rule.milestone,
+            issue_type: rule.issue_type.clone(),
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// The Slack message an issue is filed from, and who filed it.
+pub struct FiledMessage<'a> {
+    pub channel: &'a str,
+    pub ts: &'a str,
+    /// The thread replies go to; the message's own ts when it isn't in a thread
+    pub thread_ts: &'a str,
+    pub user: &'a str,
+    pub reply_mode: ReplyMode,
+}
+
+/// Files the issue, links the message to it and replies with the issue card. Once the issue is
+/// filed, failures are only logged, since a retry would file it again.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_linked_issue(
+    connection: &DatabaseConnection,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    slack_client: &SlackClient,
+    github_token: &str,
+    message: &FiledMessage<'_>,
+    repo: &str,
+    new_issue: &NewIssue,
+) -> actix_web::Result<github::Issue> {
+    let issue = issue_tracker
+        .create_issue(github_token, repo, new_issue)
+        .await?;
+
+    // The issue exists at this point. Failing the job would file it again on retry.
+    let message_issue = match (entities::message_issue::ActiveModel {
+        team_id: Set(team.id),
+        channel: Set(message.channel.to_owned()),
+        ts: Set(message.ts.to_owned()),
+        repo: Set(repo.to_owned()),
+        issue_number: Set(issue.number),
+        html_url: Set(issue.html_url.clone()),
+        ..Default::default()
+    })
+    .insert(connection)
+    .await
+    {
+        Ok(message_issue) => Some(message_issue),
+        Err(e) => {
+            log::error!("failed to link {} to {}: {}", message.ts, issue.html_url, e);
+            None
+        }
+    };
+
+    let mut note = format!("<@{}>", message.user);
+    let unassigned_logins = issue.unassigned_logins(&new_issue.assignees);
+    if !unassigned_logins.is_empty() {
+        note.push_str(&format!(
+            "\nfailed to assign on GitHub: {}",
+            unassigned_logins.join(", ")
+        ));
+    }
+    // Shown in notifications, while the card is shown in the conversation
+    let text = format!("<@{}> {}", message.user, issue.html_url);
+
+    let other_repos = other_repos(connection, team, repo)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("failed to find other repos of {}: {}", repo, e);
+            vec![]
+        });
+    let mut blocks = vec![blocks::context(&note)];
+    blocks.extend(issue_card_blocks(
+        message_issue.map(|message_issue| message_issue.id),
+        repo,
+        &issue,
+        &other_repos,
+    ));
+
+    if let Err(e) = slack_client
+        .reply(
+            message.reply_mode,
+            message.channel,
+            message.thread_ts,
+            message.user,
+            &text,
+            &blocks,
+        )
+        .await
+    {
+        log::error!("failed to reply {}: {}", issue.html_url, e);
+    }
+    Ok(issue)
+}
+
+fn file_context(team_id: i32, file: &SlackFile) -> FileContext {
+    FileContext {
+        name: file.name.clone(),
+        filetype: file.filetype.clone(),
+        permalink: file.permalink.clone(),
+        image_url: is_proxied(file).then(|| file_proxy_url(team_id, &file.id)),
+    }
+}
+
+// The rule's reply mode takes precedence over the team's.
+fn reply_mode(team: &entities::team::Model, rule: &IssueRule) -> ReplyMode {
+    let mode = rule.reply_mode.as_deref().unwrap_or(&team.reply_mode);
+    mode.parse().unwrap_or_else(|e| {
+        log::warn!("{}", e);
+        ReplyMode::default()
+    })
+}
+
+// Comment added to the existing issue when a linked message gets reacted again. Messages posted
+// after the reacted one may carry news, so they are quoted.
+fn repeat_reaction_comment(context: &IssueContext) -> String {
+    // Only linked logins mention, like in converted messages
+    let mut comment = match &context.reactioner_login {
+        Some(login) => format!("+1 from @{}", login),
+        None => format!("+1 from `@{}`", context.reactioner),
+    };
+
+    let newer_messages: Vec<&MessageContext> = context
+        .messages
+        .iter()
+        .filter(|message| message.ts.as_str() > context.message.ts.as_str())
+        .collect();
+    for message in newer_messages {
+        comment.push_str(&format!("\n\n**{}**", message.author));
+        if let Some(login) = &message.author_login {
+            comment.push_str(&format!(" (@{})", login));
+        }
+        comment.push_str(&format!("\n{}", message.text));
+    }
+
+    if !context.permalink.is_empty() {
+        comment.push_str(&format!("\n\n{}", context.permalink));
+    }
+
+    comment
+}
+
+// Number of replies taken before and after the reacted reply in a thread
+const THREAD_CONTEXT_REPLIES: usize = 2;
+const THREAD_REPLIES_LIMIT: u32 = 200;
+
+// Collects the messages around the reacted one, oldest first.
+async fn get_context_messages(
+    slack_client: &SlackClient,
+    channel: &str,
+    ts: &str,
+    include_thread: bool,
+) -> Result<Vec<SlackMessage>, SlackClientError> {
+    let reacted_message = slack_client.get_replies(channel, ts, 1).await?;
+    let thread_ts = reacted_message
+        .into_iter()
+        .next()
+        .and_then(|message| message.thread_ts);
+
+    match thread_ts {
+        // A reply in a thread
+        Some(thread_ts) if thread_ts != ts => {
+            let replies = slack_client
+                .get_replies(channel, &thread_ts, THREAD_REPLIES_LIMIT)
+                .await?;
+            Ok(thread_reply_context(replies, ts))
+        }
+
+        // A thread parent
+        Some(_) if include_thread => {
+            slack_client
+                .get_replies(channel, ts, THREAD_REPLIES_LIMIT)
+                .await
+        }
+
+        _ => {
+            let mut messages = slack_client.get_messages(channel, ts, 3).await?;
+            messages.reverse();
+            Ok(messages)
         }
     }
-    Ok(HttpResponse::Ok().body(""))
+}
+
+// Takes the thread parent and the replies surrounding the reply with `ts`.
+fn thread_reply_context(mut messages: Vec<SlackMessage>, ts: &str) -> Vec<SlackMessage> {
+    if messages.is_empty() {
+        return messages;
+    }
+
+    let mut replies = messages.split_off(1);
+    let index = replies
+        .iter()
+        .position(|message| message.ts == ts)
+        .unwrap_or(0);
+    let start = index.saturating_sub(THREAD_CONTEXT_REPLIES);
+    let end = (index + THREAD_CONTEXT_REPLIES + 1).min(replies.len());
+
+    messages.extend(replies.drain(start..end));
+    messages
+}
+
+// Slack timestamps are unix seconds with a unique suffix, e.g. "1660000000.000100"
+fn format_slack_ts(ts: &str) -> String {
+    let secs = ts
+        .split('.')
+        .next()
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .unwrap_or_default();
+    Utc.timestamp(secs, 0).to_rfc3339()
 }
 
 fn remove_head_mention(text: &str) -> String {
@@ -143,83 +934,296 @@ fn remove_head_mention(text: &str) -> String {
     re.replace(text, "").into()
 }
 
-fn humanize_slack_formatted_text(text: &str, slack_user_map: &HashMap<String, String>) -> String {
-    let text = text.replace('\n', " ");
-    let text = text.replace('`', "\\`");
-    let re = Regex::new(r"<(?P<mark>[@#!])?(?P<a>.+?)(\|(?P<b>.+?))?>").unwrap();
-    re.replace_all(&text, {
-        |caps: &Captures| {
-            if let Some(inner) = caps.name("a").map(|m| m.as_str()) {
-                match caps.name("mark").map(|m| m.as_str()).unwrap_or_default() {
-                    "@" => {
-                        let content = match slack_user_map.get(inner) {
-                            Some(s) => s,
-                            None => inner,
-                        };
-
-                        format!("@{}", content)
-                    }
+#[derive(Debug, PartialEq)]
+enum MentionCommand {
+    Help,
+    Rules,
+    Status { repo: String, number: i32 },
+    File { repo: String },
+    Link { repo: String, number: i32 },
+}
 
-                    "!" => {
-                        let _content = match caps.name("b").map(|m| m.as_str()) {
-                            Some(b) => b,
-                            None => inner,
-                        };
+fn parse_mention_command(text: &str) -> Result<MentionCommand, String> {
+    let mut args = text.split_whitespace();
+    let command = args.next().unwrap_or("help");
+    let args: Vec<&str> = args.collect();
 
-                        format!("@{}", inner)
-                    }
+    match (command, args.as_slice()) {
+        ("help", _) => Ok(MentionCommand::Help),
+        ("rules", []) => Ok(MentionCommand::Rules),
+        ("status", [url]) => {
+            let (repo, number) = parse_issue_url_arg(url)?;
+            Ok(MentionCommand::Status { repo, number })
+        }
+        ("file", [repo]) => {
+            if !is_repo(repo) {
+                return Err(format!("`{}` is not owner/repo", repo));
+            }
+            Ok(MentionCommand::File {
+                repo: repo.to_string(),
+            })
+        }
+        ("link", [url]) => {
+            let (repo, number) = parse_issue_url_arg(url)?;
+            Ok(MentionCommand::Link { repo, number })
+        }
+        _ => Err(format!("unknown command: `{}`", text.trim())),
+    }
+}
+
+// Slack wraps urls in brackets, e.g. `<https://github.com/uiur/sandbox/issues/1>`
+fn parse_issue_url_arg(arg: &str) -> Result<(String, i32), String> {
+    let url = arg.trim_start_matches('<').trim_end_matches('>');
+    let url = url.split('|').next().unwrap_or_default();
+    github::parse_issue_url(url).ok_or_else(|| format!("`{}` is not an issue url", url))
+}
 
-                    "#" => {
-                        let content = match caps.name("b").map(|m| m.as_str()) {
-                            Some(b) => b,
-                            None => inner,
-                        };
+fn mention_help() -> String {
+    [
+        "*Usage*",
+        "`@emoji-to-do rules` shows the rules of this workspace",
+        "`@emoji-to-do status <issue url>` shows the issue",
+        "`@emoji-to-do file owner/repo` files this thread as an issue to owner/repo",
+        "`@emoji-to-do link <issue url>` links this thread to the issue",
+        "`@emoji-to-do help` shows this message",
+    ]
+    .join("\n")
+}
 
-                        format!("#{}", content)
-                    }
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_app_mention(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    team_id: String,
+    user: String,
+    channel: String,
+    text: String,
+    ts: String,
+    thread_ts: Option<String>,
+) -> actix_web::Result<()> {
+    let team = entities::prelude::Team::find()
+        .filter(entities::team::Column::SlackTeamId.eq(team_id.as_str()))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorInternalServerError("team is not found"))?;
+    let slack_client = slack_client_for_team(slack_api, &team)?;
+
+    let (text, blocks) = match parse_mention_command(&remove_head_mention(&text)) {
+        Ok(MentionCommand::Help) => (mention_help(), vec![]),
 
-                    _ => {
+        Ok(MentionCommand::Rules) => {
+            let reactions = find_reactions(connection, &team).await?;
+            if reactions.is_empty() {
+                ("No rules yet.".to_string(), vec![])
+            } else {
+                (format_rules(&reactions), vec![])
+            }
+        }
+
+        Ok(MentionCommand::Status { repo, number }) => {
+            let github_token = github_token_for_team(issue_tracker, &team).await?;
+            match issue_tracker.get_issue(&github_token, &repo, number).await {
+                Ok(issue) => {
+                    let message_issue_id = find_message_issue_id(connection, &team, &repo, number)
+                        .await
+                        .map_err(ErrorInternalServerError)?;
+                    let other_repos = other_repos(connection, &team, &repo).await?;
+                    let blocks = issue_card_blocks(message_issue_id, &repo, &issue, &other_repos);
+                    (issue.html_url, blocks)
+                }
+                Err(e) => {
+                    log::error!("failed to get {}#{}: {}", repo, number, e);
+                    (
                         format!(
-                            "{}{}",
-                            caps.name("mark").map(|m| m.as_str()).unwrap_or_default(),
-                            inner
-                        )
-                    }
+                            "failed to get {}#{}. {}",
+                            escape(&repo),
+                            number,
+                            e.user_message()
+                        ),
+                        vec![],
+                    )
                 }
-            } else {
-                "".to_string()
             }
         }
-    })
-    .into()
-}
 
-async fn handle_app_mention(
-    _user: String,
-    channel: String,
-    text: String,
-) -> actix_web::Result<HttpResponse> {
-    let content = remove_head_mention(&text);
-    match content.as_str() {
-        "ping" => {
-            slack::post_message(&channel, "pong")
+        // Files the thread parent, like reacting to it with a rule of the repo
+        Ok(MentionCommand::File { repo }) => match &thread_ts {
+            Some(thread_ts) => {
+                let rule = IssueRule::for_repo(&repo);
+                match file_issue(
+                    connection,
+                    slack_api,
+                    issue_tracker,
+                    &team,
+                    &rule,
+                    &user,
+                    channel.clone(),
+                    thread_ts.clone(),
+                )
                 .await
-                .map_err(|_| actix_web::error::ErrorInternalServerError(""))?;
+                {
+                    Ok(()) => return Ok(()),
+                    Err(e) => {
+                        log::error!("failed to file to {}: {}", repo, e);
+                        let mut text = format!("failed to file this thread to {}", escape(&repo));
+                        if let Some(message) = client_error_message(&e) {
+                            text.push_str(&format!(". {}", message));
+                        }
+                        (text, vec![])
+                    }
+                }
+            }
+            None => ("Mention me in a thread to file it.".to_string(), vec![]),
+        },
+
+        Ok(MentionCommand::Link { repo, number }) => match &thread_ts {
+            Some(thread_ts) => {
+                link_thread(
+                    connection,
+                    issue_tracker,
+                    &team,
+                    &slack_client,
+                    &channel,
+                    thread_ts,
+                    &repo,
+                    number,
+                )
+                .await?
+            }
+            None => ("Mention me in a thread to link it.".to_string(), vec![]),
+        },
+
+        Err(message) => (format!("{}\n\n{}", message, mention_help()), vec![]),
+    };
+
+    // Replies go to the thread of the mention
+    let reply_ts = thread_ts.unwrap_or(ts);
+    slack_client
+        .post_message(&channel, &text, &blocks, Some(&reply_ts))
+        .await?;
+    Ok(())
+}
+
+async fn find_message_issue_id(
+    connection: &DatabaseConnection,
+    team: &entities::team::Model,
+    repo: &str,
+    number: i32,
+) -> Result<Option<i32>, DbErr> {
+    let message_issue = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::TeamId.eq(team.id))
+        .filter(entities::message_issue::Column::Repo.eq(repo))
+        .filter(entities::message_issue::Column::IssueNumber.eq(number))
+        // Several threads can be linked to the same issue. The first one is the filed message.
+        .order_by_asc(entities::message_issue::Column::Id)
+        .one(connection)
+        .await?;
+
+    Ok(message_issue.map(|message_issue| message_issue.id))
+}
+
+// Links the thread to an existing issue, so that reactions comment on it and updates of the issue
+// are posted to the thread. A thread already linked to another issue is relinked. Linking it to
+// the same issue again, e.g. on a retry, doesn't comment on the issue again.
+#[allow(clippy::too_many_arguments)]
+async fn link_thread(
+    connection: &DatabaseConnection,
+    issue_tracker: &dyn IssueTracker,
+    team: &entities::team::Model,
+    slack_client: &SlackClient,
+    channel: &str,
+    thread_ts: &str,
+    repo: &str,
+    number: i32,
+) -> actix_web::Result<(String, Vec<serde_json::Value>)> {
+    let github_token = github_token_for_team(issue_tracker, team).await?;
+    let issue = match issue_tracker.get_issue(&github_token, repo, number).await {
+        Ok(issue) => issue,
+        Err(e) => {
+            log::error!("failed to get {}#{}: {}", repo, number, e);
+            return Ok((
+                format!(
+                    "failed to get {}#{}. {}",
+                    escape(repo),
+                    number,
+                    e.user_message()
+                ),
+                vec![],
+            ));
         }
-        _ => {
-            slack::post_message(&channel, &format!("```\n{}\n```", content))
-                .await
-                .map_err(|_| actix_web::error::ErrorInternalServerError(""))?;
+    };
+
+    let existing = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::TeamId.eq(team.id))
+        .filter(entities::message_issue::Column::Channel.eq(channel))
+        .filter(entities::message_issue::Column::Ts.eq(thread_ts))
+        .one(connection)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    let already_linked = existing.as_ref().is_some_and(|message_issue| {
+        message_issue.repo == repo && message_issue.issue_number == issue.number
+    });
+    let message_issue = match existing {
+        Some(message_issue) if already_linked => Ok(message_issue),
+        Some(message_issue) => {
+            let mut active_model = message_issue.into_active_model();
+            active_model.repo = Set(repo.to_owned());
+            active_model.issue_number = Set(issue.number);
+            active_model.html_url = Set(issue.html_url.clone());
+            active_model.update(connection).await
+        }
+        None => {
+            entities::message_issue::ActiveModel {
+                team_id: Set(team.id),
+                channel: Set(channel.to_owned()),
+                ts: Set(thread_ts.to_owned()),
+                repo: Set(repo.to_owned()),
+                issue_number: Set(issue.number),
+                html_url: Set(issue.html_url.clone()),
+                ..Default::default()
+            }
+            .insert(connection)
+            .await
         }
     }
-    Ok(HttpResponse::Ok().body(""))
+    .map_err(ErrorInternalServerError)?;
+
+    let permalink = if already_linked {
+        String::new()
+    } else {
+        slack_client
+            .get_permalink(channel, thread_ts)
+            .await
+            .unwrap_or_default()
+    };
+    if !permalink.is_empty() {
+        if let Err(e) = issue_tracker
+            .create_comment(
+                &github_token,
+                repo,
+                issue.number,
+                &format!("Linked from Slack\n{}", permalink),
+            )
+            .await
+        {
+            log::error!("failed to comment on {}: {}", issue.html_url, e);
+        }
+    }
+
+    let other_repos = other_repos(connection, team, repo).await?;
+    let blocks = issue_card_blocks(Some(message_issue.id), repo, &issue, &other_repos);
+    Ok((format!("Linked this thread to {}", issue.html_url), blocks))
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
-    use super::{humanize_slack_formatted_text, remove_head_mention};
+    use super::{
+        parse_mention_command, remove_head_mention, repeat_reaction_comment, thread_reply_context,
+        MentionCommand,
+    };
+    use crate::{issue_template, slack::SlackMessage};
 
     #[test]
     fn test_remove_head_mention() {
@@ -228,29 +1232,118 @@ mod tests {
     }
 
     #[test]
-    fn test_humanize_slack_formatted_text() {
-        let mut slack_user_map = HashMap::new();
-        slack_user_map.insert("U1234".to_string(), "uiur".to_string());
+    fn test_parse_mention_command_help() {
+        assert_eq!(parse_mention_command(""), Ok(MentionCommand::Help));
+        assert_eq!(parse_mention_command("help"), Ok(MentionCommand::Help));
+        assert_eq!(parse_mention_command("help file"), Ok(MentionCommand::Help));
+    }
+
+    #[test]
+    fn test_parse_mention_command_rules() {
+        assert_eq!(parse_mention_command("rules"), Ok(MentionCommand::Rules));
+        assert!(parse_mention_command("rules all").is_err());
+    }
 
-        let text = humanize_slack_formatted_text(
-            "<@U1234> foo bar <https://github.com/uiur/sandbox/issues/1>",
-            &slack_user_map,
+    #[test]
+    fn test_parse_mention_command_status() {
+        assert_eq!(
+            parse_mention_command("status <https://github.com/uiur/sandbox/issues/12>"),
+            Ok(MentionCommand::Status {
+                repo: "uiur/sandbox".to_string(),
+                number: 12
+            })
         );
         assert_eq!(
-            text,
-            "@uiur foo bar https://github.com/uiur/sandbox/issues/1"
+            parse_mention_command("status https://github.com/uiur/sandbox/issues/12"),
+            Ok(MentionCommand::Status {
+                repo: "uiur/sandbox".to_string(),
+                number: 12
+            })
+        );
+        assert!(parse_mention_command("status").is_err());
+        assert!(parse_mention_command("status <https://example.com>").is_err());
+    }
+
+    #[test]
+    fn test_parse_mention_command_file() {
+        assert_eq!(
+            parse_mention_command("file uiur/sandbox"),
+            Ok(MentionCommand::File {
+                repo: "uiur/sandbox".to_string()
+            })
         );
+        assert!(parse_mention_command("file").is_err());
+        assert!(parse_mention_command("file sandbox").is_err());
+    }
+
+    #[test]
+    fn test_parse_mention_command_link() {
+        assert_eq!(
+            parse_mention_command("link <https://github.com/uiur/sandbox/issues/3|uiur/sandbox#3>"),
+            Ok(MentionCommand::Link {
+                repo: "uiur/sandbox".to_string(),
+                number: 3
+            })
+        );
+        assert!(parse_mention_command("link uiur/sandbox#3").is_err());
+    }
+
+    #[test]
+    fn test_parse_mention_command_unknown() {
+        assert!(parse_mention_command("ping").is_err());
+        assert!(parse_mention_command("files uiur/sandbox").is_err());
+    }
+
+    fn message(ts: &str) -> SlackMessage {
+        SlackMessage {
+            user: "U1234".to_string(),
+            text: format!("message at {}", ts),
+            ts: ts.to_string(),
+            thread_ts: Some("1.0".to_string()),
+            files: vec![],
+        }
+    }
 
-        let text = humanize_slack_formatted_text("```\nfoo bar\n```", &slack_user_map);
-        assert_eq!(text, "\\`\\`\\` foo bar \\`\\`\\`");
+    #[test]
+    fn test_thread_reply_context() {
+        let thread = || -> Vec<SlackMessage> {
+            ["1.0", "2.0", "3.0", "4.0", "5.0", "6.0", "7.0", "8.0"]
+                .iter()
+                .map(|ts| message(ts))
+                .collect()
+        };
+        let timestamps = |messages: Vec<SlackMessage>| -> Vec<String> {
+            messages.into_iter().map(|m| m.ts).collect()
+        };
+
+        assert_eq!(
+            timestamps(thread_reply_context(thread(), "5.0")),
+            vec!["1.0", "3.0", "4.0", "5.0", "6.0", "7.0"]
+        );
+        assert_eq!(
+            timestamps(thread_reply_context(thread(), "2.0")),
+            vec!["1.0", "2.0", "3.0", "4.0"]
+        );
+        assert_eq!(
+            timestamps(thread_reply_context(thread(), "8.0")),
+            vec!["1.0", "6.0", "7.0", "8.0"]
+        );
+        assert!(thread_reply_context(vec![], "2.0").is_empty());
+    }
 
-        let text = humanize_slack_formatted_text("<!here>", &slack_user_map);
-        assert_eq!(text, "@here");
+    #[test]
+    fn test_repeat_reaction_comment() {
+        let mut context = issue_template::sample_context();
+        assert_eq!(
+            repeat_reaction_comment(&context),
+            "+1 from `@carol`\n\n**bob**\nlooks like the migration step times out\n\nhttps://example.slack.com/archives/C024BE7LR/p1660000000000100"
+        );
 
-        let text = humanize_slack_formatted_text("<!subteam^SAZ94GDB8>", &slack_user_map);
-        assert_eq!(text, "@subteam^SAZ94GDB8");
+        context.message = context.messages[1].clone();
+        context.permalink = "".to_string();
+        assert_eq!(repeat_reaction_comment(&context), "+1 from `@carol`");
 
-        let text = humanize_slack_formatted_text("<#C024BE7LR>", &slack_user_map);
-        assert_eq!(text, "#C024BE7LR");
+        context.reactioner_login = Some("carol-dev".to_string());
+        assert_eq!(repeat_reaction_comment(&context), "+1 from @carol-dev");
     }
 }
@@ -0,0 +1,479 @@
+//! HTTP client shared by the Slack and GitHub clients.
+//! Requests are retried when the API is rate limited or temporarily unavailable, and the rate
+//! limits reported by the APIs are tracked per token for `/metrics`.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{header::HeaderMap, Method, StatusCode};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::clock::unix_now;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+// Longer waits are left to the job queue, which retries the whole job later
+const MAX_RETRY_WAIT_SECS: i64 = 60;
+// Installation tokens expire hourly, so keys of tokens unused this long are dropped
+const RATE_LIMIT_RETENTION_SECS: i64 = 2 * 60 * 60;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct RateLimit {
+    requests: u64,
+    retries: u64,
+    rate_limited: u64,
+    // From the `X-RateLimit-*` headers, which GitHub sends and Slack doesn't
+    limit: Option<i64>,
+    remaining: Option<i64>,
+    reset_at: Option<i64>,
+    used_at: i64,
+}
+
+// Name, type and value of each metric
+type Metric = (&'static str, &'static str, fn(&RateLimit) -> Option<i64>);
+
+const METRICS: [Metric; 6] = [
+    ("http_client_requests_total", "counter", |r| {
+        Some(r.requests as i64)
+    }),
+    ("http_client_retries_total", "counter", |r| {
+        Some(r.retries as i64)
+    }),
+    ("http_client_rate_limited_total", "counter", |r| {
+        Some(r.rate_limited as i64)
+    }),
+    ("http_client_rate_limit_limit", "gauge", |r| r.limit),
+    ("http_client_rate_limit_remaining", "gauge", |r| r.remaining),
+    (
+        "http_client_rate_limit_reset_timestamp_seconds",
+        "gauge",
+        |r| r.reset_at,
+    ),
+];
+
+/// Pooled HTTP client with timeouts and retries. Clones share the pool and the rate limit state.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    // (host, token fingerprint) to what is known about its rate limit
+    rate_limits: Arc<Mutex<BTreeMap<(String, String), RateLimit>>>,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build http client");
+
+        HttpClient {
+            client,
+            rate_limits: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn get(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    pub fn patch(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    fn request(&self, method: Method, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        RequestBuilder {
+            http: self.clone(),
+            builder: self.client.request(method, url),
+        }
+    }
+
+    async fn execute(&self, request: reqwest::Request) -> reqwest::Result<reqwest::Response> {
+        let key = rate_limit_key(&request);
+        if let Some(wait) = self.wait_for_reset(&key, unix_now()) {
+            log::warn!("rate limited by {}, waiting {:?}", key.0, wait);
+            actix_rt::time::sleep(wait).await;
+        }
+
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let idempotent = is_idempotent(request.method());
+            // A streamed body can't be sent twice
+            let next = request.try_clone();
+            let result = self.client.execute(request).await;
+
+            let delay = match &result {
+                Ok(resp) => {
+                    self.record_response(&key, resp.status(), resp.headers());
+                    retry_delay(
+                        resp.status(),
+                        resp.headers(),
+                        idempotent,
+                        attempt,
+                        unix_now(),
+                        jitter(),
+                    )
+                }
+                // A request that couldn't connect never reached the API
+                Err(e) if e.is_connect() || (e.is_timeout() && idempotent) => {
+                    self.update_rate_limit(&key, |rate_limit| rate_limit.requests += 1);
+                    Some(backoff(attempt, jitter()))
+                }
+                Err(_) => {
+                    self.update_rate_limit(&key, |rate_limit| rate_limit.requests += 1);
+                    None
+                }
+            };
+
+            match (delay, next) {
+                (Some(delay), Some(next)) if attempt < MAX_RETRIES => {
+                    log::warn!("retrying {} {} in {:?}", next.method(), next.url(), delay);
+                    self.update_rate_limit(&key, |rate_limit| rate_limit.retries += 1);
+                    actix_rt::time::sleep(delay).await;
+                    request = next;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    fn update_rate_limit(&self, key: &(String, String), update: impl FnOnce(&mut RateLimit)) {
+        let now = unix_now();
+        let mut rate_limits = self.rate_limits.lock().unwrap();
+        if !rate_limits.contains_key(key) {
+            rate_limits
+                .retain(|_, rate_limit| rate_limit.used_at > now - RATE_LIMIT_RETENTION_SECS);
+        }
+        let rate_limit = rate_limits.entry(key.clone()).or_default();
+        update(rate_limit);
+        rate_limit.used_at = now;
+    }
+
+    fn record_response(&self, key: &(String, String), status: StatusCode, headers: &HeaderMap) {
+        self.update_rate_limit(key, |rate_limit| {
+            rate_limit.requests += 1;
+            if is_rate_limited(status, headers) {
+                rate_limit.rate_limited += 1;
+            }
+            if let Some(limit) = header_i64(headers, "x-ratelimit-limit") {
+                rate_limit.limit = Some(limit);
+            }
+            if let Some(remaining) = header_i64(headers, "x-ratelimit-remaining") {
+                rate_limit.remaining = Some(remaining);
+            }
+            if let Some(reset_at) = header_i64(headers, "x-ratelimit-reset") {
+                rate_limit.reset_at = Some(reset_at);
+            }
+        });
+    }
+
+    // A token that used up its quota fails until the reset, so wait for it when it's close.
+    fn wait_for_reset(&self, key: &(String, String), now: i64) -> Option<Duration> {
+        let rate_limits = self.rate_limits.lock().unwrap();
+        let rate_limit = rate_limits.get(key)?;
+        let wait = rate_limit.reset_at? - now;
+        (rate_limit.remaining == Some(0) && wait > 0 && wait <= MAX_RETRY_WAIT_SECS)
+            .then(|| Duration::from_secs(wait as u64))
+    }
+
+    /// Request and rate limit counters per API host and token, in the Prometheus text format.
+    pub fn render_metrics(&self) -> String {
+        let rate_limits = self.rate_limits.lock().unwrap();
+        let mut metrics = String::new();
+        for (name, kind, value) in METRICS {
+            let _ = writeln!(metrics, "# TYPE {} {}", name, kind);
+            for ((host, token), rate_limit) in rate_limits.iter() {
+                if let Some(value) = value(rate_limit) {
+                    let _ = writeln!(
+                        metrics,
+                        "{}{{host=\"{}\",token=\"{}\"}} {}",
+                        name, host, token, value
+                    );
+                }
+            }
+        }
+        metrics
+    }
+}
+
+/// `reqwest::RequestBuilder` that sends through `HttpClient`.
+pub struct RequestBuilder {
+    http: HttpClient,
+    builder: reqwest::RequestBuilder,
+}
+
+impl RequestBuilder {
+    pub fn query<T: Serialize + ?Sized>(self, query: &T) -> Self {
+        self.map(|builder| builder.query(query))
+    }
+
+    pub fn header(self, key: &str, value: &str) -> Self {
+        self.map(|builder| builder.header(key, value))
+    }
+
+    pub fn bearer_auth(self, token: impl std::fmt::Display) -> Self {
+        self.map(|builder| builder.bearer_auth(token))
+    }
+
+    pub fn json<T: Serialize + ?Sized>(self, json: &T) -> Self {
+        self.map(|builder| builder.json(json))
+    }
+
+    fn map(self, f: impl FnOnce(reqwest::RequestBuilder) -> reqwest::RequestBuilder) -> Self {
+        RequestBuilder {
+            http: self.http,
+            builder: f(self.builder),
+        }
+    }
+
+    pub async fn send(self) -> reqwest::Result<reqwest::Response> {
+        let request = self.builder.build()?;
+        self.http.execute(request).await
+    }
+}
+
+// Tokens are identified by a fingerprint, so they don't show up in metrics and logs.
+fn rate_limit_key(request: &reqwest::Request) -> (String, String) {
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let token = match request.headers().get(reqwest::header::AUTHORIZATION) {
+        Some(authorization) => hex::encode(&Sha256::digest(authorization.as_bytes())[..4]),
+        None => "none".to_string(),
+    };
+    (host, token)
+}
+
+// A POST that failed on the server may have had an effect, e.g. created an issue.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+    )
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+// GitHub answers 403 to both missing permissions and its secondary rate limit. The latter comes
+// with `Retry-After` or an exhausted `X-RateLimit-Remaining`.
+// https://docs.github.com/en/rest/overview/resources-in-the-rest-api#rate-limiting
+// https://api.slack.com/docs/rate-limits
+fn is_rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN
+            && (headers.contains_key(reqwest::header::RETRY_AFTER)
+                || header_i64(headers, "x-ratelimit-remaining") == Some(0)))
+}
+
+// Rate limited requests were not processed, so they are retried whatever the method is.
+fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    idempotent: bool,
+    attempt: u32,
+    now: i64,
+    jitter: f64,
+) -> Option<Duration> {
+    let retry_after = header_i64(headers, reqwest::header::RETRY_AFTER.as_str());
+    if is_rate_limited(status, headers) {
+        let wait = retry_after
+            .or_else(|| header_i64(headers, "x-ratelimit-reset").map(|reset_at| reset_at - now));
+        return match wait {
+            Some(wait) if wait > MAX_RETRY_WAIT_SECS => None,
+            Some(wait) => Some(Duration::from_secs(wait.max(1) as u64)),
+            None => Some(backoff(attempt, jitter)),
+        };
+    }
+
+    if status.is_server_error() && idempotent {
+        return match retry_after {
+            Some(wait) if wait > MAX_RETRY_WAIT_SECS => None,
+            Some(wait) => Some(Duration::from_secs(wait.max(0) as u64)),
+            None => Some(backoff(attempt, jitter)),
+        };
+    }
+
+    None
+}
+
+// 0.5s, 1s, 2s, ... scaled by a random factor between 0.5 and 1, so that clients hit by the same
+// outage don't retry in lockstep.
+fn backoff(attempt: u32, jitter: f64) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(2_u32.saturating_pow(attempt.min(16)))
+        .mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+// A random number in [0, 1]
+fn jitter() -> f64 {
+    let mut bytes = [0; 4];
+    match openssl::rand::rand_bytes(&mut bytes) {
+        Ok(()) => u32::from_le_bytes(bytes) as f64 / u32::MAX as f64,
+        Err(_) => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{header::HeaderMap, StatusCode};
+
+    use super::{backoff, retry_delay, HttpClient, RateLimit};
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let now = 1_660_000_000;
+        let cases = [
+            (StatusCode::OK, headers(&[]), true, None),
+            (StatusCode::NOT_FOUND, headers(&[]), true, None),
+            // Slack
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                headers(&[("retry-after", "30")]),
+                false,
+                Some(Duration::from_secs(30)),
+            ),
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                headers(&[("retry-after", "600")]),
+                false,
+                None,
+            ),
+            // GitHub's primary and secondary rate limits
+            (
+                StatusCode::FORBIDDEN,
+                headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1660000020"),
+                ]),
+                false,
+                Some(Duration::from_secs(20)),
+            ),
+            (
+                StatusCode::FORBIDDEN,
+                headers(&[("retry-after", "5")]),
+                false,
+                Some(Duration::from_secs(5)),
+            ),
+            (
+                StatusCode::FORBIDDEN,
+                headers(&[("x-ratelimit-remaining", "10")]),
+                true,
+                None,
+            ),
+            // Server errors
+            (
+                StatusCode::BAD_GATEWAY,
+                headers(&[]),
+                true,
+                Some(Duration::from_secs(1)),
+            ),
+            (StatusCode::BAD_GATEWAY, headers(&[]), false, None),
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                headers(&[("retry-after", "3")]),
+                true,
+                Some(Duration::from_secs(3)),
+            ),
+        ];
+
+        for (status, headers, idempotent, expected) in cases {
+            assert_eq!(
+                retry_delay(status, &headers, idempotent, 1, now, 1.0),
+                expected,
+                "{} {:?}",
+                status,
+                headers
+            );
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff(2, 1.0), Duration::from_secs(2));
+        assert_eq!(backoff(2, 0.0), Duration::from_secs(1));
+        assert_eq!(backoff(100, 1.0), backoff(16, 1.0));
+    }
+
+    #[test]
+    fn test_wait_for_reset_and_metrics() {
+        let http = HttpClient::new();
+        let key = ("api.github.com".to_string(), "0a1b2c3d".to_string());
+        http.update_rate_limit(&key, |rate_limit| {
+            *rate_limit = RateLimit {
+                requests: 3,
+                retries: 1,
+                rate_limited: 1,
+                limit: Some(5000),
+                remaining: Some(0),
+                reset_at: Some(1_660_000_030),
+                ..Default::default()
+            }
+        });
+
+        assert_eq!(
+            http.wait_for_reset(&key, 1_660_000_000),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(http.wait_for_reset(&key, 1_660_000_030), None);
+        assert_eq!(http.wait_for_reset(&key, 1_650_000_000), None);
+
+        let metrics = http.render_metrics();
+        assert!(metrics.contains(
+            "http_client_requests_total{host=\"api.github.com\",token=\"0a1b2c3d\"} 3\n"
+        ));
+        assert!(metrics.contains(
+            "http_client_rate_limit_remaining{host=\"api.github.com\",token=\"0a1b2c3d\"} 0\n"
+        ));
+    }
+
+    #[test]
+    fn test_stale_rate_limits_are_dropped() {
+        let http = HttpClient::new();
+        let old = ("api.github.com".to_string(), "0a1b2c3d".to_string());
+        let new = ("api.github.com".to_string(), "4e5f6a7b".to_string());
+        http.update_rate_limit(&old, |rate_limit| rate_limit.requests += 1);
+        http.rate_limits
+            .lock()
+            .unwrap()
+            .get_mut(&old)
+            .unwrap()
+            .used_at -= super::RATE_LIMIT_RETENTION_SECS;
+
+        http.update_rate_limit(&new, |rate_limit| rate_limit.requests += 1);
+
+        let rate_limits = http.rate_limits.lock().unwrap();
+        assert!(!rate_limits.contains_key(&old));
+        assert!(rate_limits.contains_key(&new));
+    }
+}
@@ -0,0 +1,223 @@
+use std::sync::OnceLock;
+
+use handlebars::Handlebars;
+use serde::Serialize;
+
+pub const DEFAULT_TITLE_TEMPLATE: &str = "{{message.text}}";
+pub const DEFAULT_BODY_TEMPLATE: &str = "{{#each messages}}**{{author}}**{{#if author_login}} (@{{author_login}}){{/if}}\n{{text}}\n\n{{/each}}{{permalink}}\
+    {{#each files}}\n- [{{name}}]({{permalink}}) ({{filetype}}){{#if image_url}}\n  ![{{name}}]({{image_url}}){{/if}}{{/each}}";
+
+/// Variables available to the title and body templates of a reaction rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueContext {
+    /// The reacted message
+    pub message: MessageContext,
+    /// The reacted message and its surrounding messages, oldest first
+    pub messages: Vec<MessageContext>,
+    /// Distinct authors of `messages`
+    pub authors: Vec<String>,
+    /// Files shared in `messages`
+    pub files: Vec<FileContext>,
+    pub channel: String,
+    pub permalink: String,
+    pub reactioner: String,
+    /// GitHub login linked to the reactioner
+    pub reactioner_login: Option<String>,
+    /// Time the reacted message was posted, in RFC 3339
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageContext {
+    pub author: String,
+    /// GitHub login linked to the author
+    pub author_login: Option<String>,
+    /// GitHub Markdown converted from the message
+    pub text: String,
+    /// The message as a single line of plain text. The title template sees it as `text`.
+    #[serde(skip)]
+    pub title_text: String,
+    pub ts: String,
+    pub files: Vec<FileContext>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileContext {
+    pub name: String,
+    pub filetype: String,
+    /// The file on Slack, only visible to members of the workspace
+    pub permalink: String,
+    /// Publicly viewable url of an image, served by the file proxy
+    pub image_url: Option<String>,
+}
+
+impl IssueContext {
+    fn for_title(&self) -> IssueContext {
+        let mut context = self.clone();
+        for message in std::iter::once(&mut context.message).chain(&mut context.messages) {
+            message.text = message.title_text.clone();
+        }
+        context
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderedIssue {
+    pub title: String,
+    pub body: String,
+}
+
+static REGISTRY: OnceLock<Handlebars<'static>> = OnceLock::new();
+
+fn registry() -> &'static Handlebars<'static> {
+    REGISTRY.get_or_init(|| {
+        let mut handlebars = Handlebars::new();
+        // Issues are markdown, not html
+        handlebars.register_escape_fn(handlebars::no_escape);
+        // A misspelled variable fails instead of rendering as an empty string
+        handlebars.set_strict_mode(true);
+        handlebars
+    })
+}
+
+pub fn render(
+    title_template: Option<&str>,
+    body_template: Option<&str>,
+    context: &IssueContext,
+) -> Result<RenderedIssue, handlebars::RenderError> {
+    let handlebars = registry();
+    let title = handlebars.render_template(
+        title_template.unwrap_or(DEFAULT_TITLE_TEMPLATE),
+        &context.for_title(),
+    )?;
+    let body =
+        handlebars.render_template(body_template.unwrap_or(DEFAULT_BODY_TEMPLATE), context)?;
+
+    Ok(RenderedIssue {
+        title: title.split_whitespace().collect::<Vec<_>>().join(" "),
+        body,
+    })
+}
+
+/// Checks that the templates compile and render against sample variables.
+pub fn validate(
+    title_template: Option<&str>,
+    body_template: Option<&str>,
+) -> Result<(), handlebars::RenderError> {
+    // Blocks over files are only rendered, and so checked, when there are some
+    let mut context = sample_context();
+    context.files = vec![FileContext {
+        name: "screenshot.png".to_string(),
+        filetype: "png".to_string(),
+        permalink: "https://example.slack.com/files/U1/F1/screenshot.png".to_string(),
+        image_url: Some("https://emoji-to-do.com/files/1/F1?signature=abc".to_string()),
+    }];
+    context.message.files = context.files.clone();
+    context.messages[0].files = context.files.clone();
+
+    render(title_template, body_template, &context).map(|_| ())
+}
+
+pub fn sample_context() -> IssueContext {
+    let messages = vec![
+        MessageContext {
+            author: "alice".to_string(),
+            author_login: Some("alice-dev".to_string()),
+            text: "the build on **main** is failing".to_string(),
+            title_text: "the build on main is failing".to_string(),
+            ts: "1660000000.000100".to_string(),
+            files: vec![],
+        },
+        MessageContext {
+            author: "bob".to_string(),
+            author_login: None,
+            text: "looks like the migration step times out".to_string(),
+            title_text: "looks like the migration step times out".to_string(),
+            ts: "1660000060.000200".to_string(),
+            files: vec![],
+        },
+    ];
+
+    IssueContext {
+        message: messages[0].clone(),
+        authors: vec!["alice".to_string(), "bob".to_string()],
+        files: vec![],
+        messages,
+        channel: "C024BE7LR".to_string(),
+        permalink: "https://example.slack.com/archives/C024BE7LR/p1660000000000100".to_string(),
+        reactioner: "carol".to_string(),
+        reactioner_login: None,
+        timestamp: "2022-08-08T23:06:40+00:00".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, sample_context, validate, FileContext};
+
+    #[test]
+    fn test_render_default_templates() {
+        let issue = render(None, None, &sample_context()).unwrap();
+        assert_eq!(issue.title, "the build on main is failing");
+        assert_eq!(
+            issue.body,
+            "**alice** (@alice-dev)\nthe build on **main** is failing\n\n**bob**\nlooks like the migration step times out\n\nhttps://example.slack.com/archives/C024BE7LR/p1660000000000100"
+        );
+    }
+
+    #[test]
+    fn test_render_default_templates_with_files() {
+        let mut context = sample_context();
+        context.files = vec![
+            FileContext {
+                name: "screenshot.png".to_string(),
+                filetype: "png".to_string(),
+                permalink: "https://example.slack.com/files/U1/F1/screenshot.png".to_string(),
+                image_url: Some("https://emoji-to-do.com/files/1/F1?signature=abc".to_string()),
+            },
+            FileContext {
+                name: "build.log".to_string(),
+                filetype: "text".to_string(),
+                permalink: "https://example.slack.com/files/U1/F2/build.log".to_string(),
+                image_url: None,
+            },
+        ];
+
+        let issue = render(None, None, &context).unwrap();
+        assert!(issue.body.ends_with(
+            "p1660000000000100\n\
+             - [screenshot.png](https://example.slack.com/files/U1/F1/screenshot.png) (png)\n  \
+             ![screenshot.png](https://emoji-to-do.com/files/1/F1?signature=abc)\n\
+             - [build.log](https://example.slack.com/files/U1/F2/build.log) (text)"
+        ));
+    }
+
+    #[test]
+    fn test_render_custom_templates() {
+        let issue = render(
+            Some("[{{reactioner}}]\n{{message.text}} <{{channel}}>"),
+            Some("{{#each authors}}@{{this}} {{/each}}\n{{timestamp}}"),
+            &sample_context(),
+        )
+        .unwrap();
+        assert_eq!(
+            issue.title,
+            "[carol] the build on main is failing <C024BE7LR>"
+        );
+        assert_eq!(issue.body, "@alice @bob \n2022-08-08T23:06:40+00:00");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(None, None).is_ok());
+        assert!(validate(
+            Some("{{message.text}}"),
+            Some("{{#each messages}}{{text}}{{/each}}")
+        )
+        .is_ok());
+        assert!(validate(Some("{{#each messages}}"), None).is_err());
+        assert!(validate(None, Some("{{unknown_helper message}}")).is_err());
+        assert!(validate(Some("{{mesage.text}}"), None).is_err());
+        assert!(validate(None, Some("{{#each files}}{{nmae}}{{/each}}")).is_err());
+    }
+}
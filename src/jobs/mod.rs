@@ -0,0 +1,324 @@
+use std::{sync::Arc, time::Duration};
+
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::unix_now,
+    entities,
+    github::IssueTracker,
+    handlers::{
+        github_webhook,
+        slack_interactions::{self, IssueCardAction, IssueFormSubmission},
+        webhook,
+    },
+    services::Services,
+    slack::{SlackApi, SlackItem},
+};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_DEAD: &str = "dead";
+
+const MAX_ATTEMPTS: i32 = 5;
+const BASE_BACKOFF_SECS: i64 = 10;
+const MAX_BACKOFF_SECS: i64 = 60 * 60;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "type")]
+pub enum Job {
+    ReactionAdded {
+        reaction_id: i32,
+        user: String,
+        item: SlackItem,
+    },
+    AppMention {
+        team_id: String,
+        user: String,
+        channel: String,
+        text: String,
+        #[serde(default)]
+        ts: String,
+        #[serde(default)]
+        thread_ts: Option<String>,
+    },
+    IssueUpdated {
+        message_issue_id: i32,
+        text: String,
+        closed: bool,
+    },
+    IssueCardAction(IssueCardAction),
+    IssueFormSubmitted(IssueFormSubmission),
+}
+
+pub async fn enqueue(
+    connection: &DatabaseConnection,
+    slack_team_id: &str,
+    job: &Job,
+) -> Result<entities::job::Model, DbErr> {
+    let payload = serde_json::to_string(job).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    entities::job::ActiveModel {
+        slack_team_id: Set(slack_team_id.to_owned()),
+        payload: Set(payload),
+        status: Set(STATUS_PENDING.to_owned()),
+        attempts: Set(0),
+        run_at: Set(unix_now()),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+}
+
+/// Puts a dead job back into the queue with a fresh set of attempts.
+pub async fn retry(
+    connection: &DatabaseConnection,
+    job: entities::job::Model,
+) -> Result<entities::job::Model, DbErr> {
+    let mut active_model = job.into_active_model();
+    active_model.status = Set(STATUS_PENDING.to_owned());
+    active_model.attempts = Set(0);
+    active_model.run_at = Set(unix_now());
+    active_model.locked_at = Set(None);
+    active_model.update(connection).await
+}
+
+pub fn spawn_workers(connection: DatabaseConnection, services: Services, count: usize) {
+    if count == 0 {
+        return;
+    }
+
+    actix_rt::spawn(async move {
+        // Jobs left running by a previous process will never finish, so run them again.
+        if let Err(e) = entities::prelude::Job::update_many()
+            .col_expr(entities::job::Column::Status, Expr::value(STATUS_PENDING))
+            .filter(entities::job::Column::Status.eq(STATUS_RUNNING))
+            .exec(&connection)
+            .await
+        {
+            log::error!("failed to reset running jobs: {}", e);
+        }
+
+        for _ in 0..count {
+            actix_rt::spawn(work(connection.clone(), services.clone()));
+        }
+    });
+}
+
+async fn work(connection: DatabaseConnection, services: Services) {
+    loop {
+        match fetch_next(&connection).await {
+            Ok(Some(job)) => perform(&connection, &services, job).await,
+            Ok(None) => actix_rt::time::sleep(POLL_INTERVAL).await,
+            Err(e) => {
+                log::error!("failed to fetch job: {}", e);
+                actix_rt::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Runs the jobs that are due one by one until none is left, and returns how many ran.
+pub async fn work_off(
+    connection: &DatabaseConnection,
+    services: &Services,
+) -> Result<usize, DbErr> {
+    let mut count = 0;
+    while let Some(job) = fetch_next(connection).await? {
+        perform(connection, services, job).await;
+        count += 1;
+    }
+    Ok(count)
+}
+
+async fn fetch_next(
+    connection: &DatabaseConnection,
+) -> Result<Option<entities::job::Model>, DbErr> {
+    let now = unix_now();
+    let candidate = entities::prelude::Job::find()
+        .filter(entities::job::Column::Status.eq(STATUS_PENDING))
+        .filter(entities::job::Column::RunAt.lte(now))
+        .order_by_asc(entities::job::Column::RunAt)
+        .order_by_asc(entities::job::Column::Id)
+        .one(connection)
+        .await?;
+
+    let candidate = match candidate {
+        Some(candidate) => candidate,
+        None => return Ok(None),
+    };
+
+    // Another worker may have taken the job in the meantime
+    let result = entities::prelude::Job::update_many()
+        .col_expr(entities::job::Column::Status, Expr::value(STATUS_RUNNING))
+        .col_expr(
+            entities::job::Column::Attempts,
+            Expr::col(entities::job::Column::Attempts).add(1),
+        )
+        .col_expr(entities::job::Column::LockedAt, Expr::value(now))
+        .filter(entities::job::Column::Id.eq(candidate.id))
+        .filter(entities::job::Column::Status.eq(STATUS_PENDING))
+        .exec(connection)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    entities::prelude::Job::find_by_id(candidate.id)
+        .one(connection)
+        .await
+}
+
+async fn perform(connection: &DatabaseConnection, services: &Services, job: entities::job::Model) {
+    let result = match serde_json::from_str::<Job>(&job.payload) {
+        Ok(payload) => {
+            log::debug!("perform job {}: {:?}", job.id, payload);
+            run(
+                connection,
+                &services.slack_api,
+                services.issue_tracker.as_ref(),
+                payload,
+            )
+            .await
+            .map_err(|e| {
+                if let Some(body) = webhook::client_error_body(&e) {
+                    log::error!("job {} response: {}", job.id, body);
+                }
+                (e.to_string(), webhook::is_retryable(&e))
+            })
+        }
+        // The payload reads the same on every attempt
+        Err(e) => Err((format!("invalid payload: {}", e), false)),
+    };
+
+    let saved = match result {
+        Ok(()) => entities::prelude::Job::delete_by_id(job.id)
+            .exec(connection)
+            .await
+            .map(|_| ()),
+        Err((message, retryable)) => {
+            log::error!("job {} failed: {}", job.id, message);
+
+            let attempts = job.attempts;
+            let mut active_model = job.into_active_model();
+            // Errors like a missing scope fail every attempt, so there's no point in waiting
+            if attempts >= MAX_ATTEMPTS || !retryable {
+                active_model.status = Set(STATUS_DEAD.to_owned());
+            } else {
+                active_model.status = Set(STATUS_PENDING.to_owned());
+                active_model.run_at = Set(unix_now() + backoff_secs(attempts));
+            }
+            active_model.locked_at = Set(None);
+            active_model.last_error = Set(Some(message));
+            active_model.update(connection).await.map(|_| ())
+        }
+    };
+
+    if let Err(e) = saved {
+        log::error!("failed to save job result: {}", e);
+    }
+}
+
+async fn run(
+    connection: &DatabaseConnection,
+    slack_api: &Arc<dyn SlackApi>,
+    issue_tracker: &dyn IssueTracker,
+    job: Job,
+) -> actix_web::Result<()> {
+    match job {
+        Job::ReactionAdded {
+            reaction_id,
+            user,
+            item,
+        } => {
+            webhook::handle_reaction_added(
+                connection,
+                slack_api,
+                issue_tracker,
+                reaction_id,
+                user,
+                item,
+            )
+            .await
+        }
+        Job::AppMention {
+            team_id,
+            user,
+            channel,
+            text,
+            ts,
+            thread_ts,
+        } => {
+            webhook::handle_app_mention(
+                connection,
+                slack_api,
+                issue_tracker,
+                team_id,
+                user,
+                channel,
+                text,
+                ts,
+                thread_ts,
+            )
+            .await
+        }
+        Job::IssueUpdated {
+            message_issue_id,
+            text,
+            closed,
+        } => {
+            github_webhook::handle_issue_updated(
+                connection,
+                slack_api,
+                message_issue_id,
+                text,
+                closed,
+            )
+            .await
+        }
+        Job::IssueCardAction(action) => {
+            slack_interactions::handle_issue_card_action(
+                connection,
+                slack_api,
+                issue_tracker,
+                action,
+            )
+            .await
+        }
+        Job::IssueFormSubmitted(submission) => {
+            slack_interactions::handle_issue_form_submission(
+                connection,
+                slack_api,
+                issue_tracker,
+                submission,
+            )
+            .await
+        }
+    }
+}
+
+// 10s, 20s, 40s, ... capped at an hour
+fn backoff_secs(attempts: i32) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    (BASE_BACKOFF_SECS * 2_i64.pow(exponent)).min(MAX_BACKOFF_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backoff_secs;
+
+    #[test]
+    fn test_backoff_secs() {
+        assert_eq!(backoff_secs(1), 10);
+        assert_eq!(backoff_secs(2), 20);
+        assert_eq!(backoff_secs(3), 40);
+        assert_eq!(backoff_secs(10), 60 * 60);
+        assert_eq!(backoff_secs(100), 60 * 60);
+    }
+}
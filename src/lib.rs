@@ -11,18 +11,34 @@ use actix_web::{
     web, App, HttpServer,
 };
 use handlebars::Handlebars;
-use handlers::{api, github_auth, hello, root, slack_auth, webhook};
+use handlers::{
+    api, github_auth, github_webhook, hello, metrics, root, slack_auth, slack_commands,
+    slack_files, slack_interactions, webhook,
+};
+use middleware::{github_signature::GithubSignature, slack_signature::SlackSignature};
 use sea_orm::DatabaseConnection;
 
+mod clock;
 pub mod entities;
-mod github;
+pub mod github;
 mod handlers;
-mod slack;
+pub mod http_client;
+mod issue_template;
+mod jobs;
+mod middleware;
+mod mrkdwn;
+pub mod secret;
+mod services;
+pub mod slack;
 pub mod token;
 
+pub use jobs::work_off;
+pub use services::Services;
+
 pub fn run(
     listener: TcpListener,
     connection: DatabaseConnection,
+    services: Services,
 ) -> Result<Server, std::io::Error> {
     let mut handlebars = Handlebars::new();
     handlebars
@@ -32,6 +48,19 @@ pub fn run(
 
     let master_key = env::var("MASTER_KEY").expect("MASTER_KEY is expected");
     let secret_key = Key::derive_from(master_key.as_bytes());
+    let slack_signing_secret =
+        env::var("SLACK_SIGNING_SECRET").expect("SLACK_SIGNING_SECRET is expected");
+    let github_webhook_secret =
+        env::var("GITHUB_WEBHOOK_SECRET").expect("GITHUB_WEBHOOK_SECRET is expected");
+
+    let job_workers = env::var("JOB_WORKERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
+    jobs::spawn_workers(connection.clone(), services.clone(), job_workers);
+    let http_client = web::Data::new(services.http);
+    let slack_api = web::Data::from(services.slack_api);
+    let issue_tracker = web::Data::from(services.issue_tracker);
 
     let connection = web::Data::new(connection);
     let server = HttpServer::new(move || {
@@ -48,6 +77,9 @@ pub fn run(
         App::new()
             .app_data(json_config)
             .app_data(connection.clone())
+            .app_data(http_client.clone())
+            .app_data(slack_api.clone())
+            .app_data(issue_tracker.clone())
             .app_data(handlebars_ref.clone())
             .wrap(Logger::default())
             .wrap(
@@ -61,6 +93,7 @@ pub fn run(
             .wrap(cors)
             .route("/", web::get().to(root::get_index))
             .route("/hello", web::get().to(hello::get_hello))
+            .route("/metrics", web::get().to(metrics::get_metrics))
             .route("/auth/slack", web::get().to(slack_auth::get_slack_auth))
             .route(
                 "/auth/slack/callback",
@@ -71,13 +104,32 @@ pub fn run(
                 "/auth/github/callback",
                 web::get().to(github_auth::github_auth_callback),
             )
+            .service(
+                web::scope("/webhook/slack")
+                    .wrap(SlackSignature::new(slack_signing_secret.clone()))
+                    .route("/events", web::post().to(webhook::create_slack_events))
+                    .route(
+                        "/commands",
+                        web::post().to(slack_commands::create_slack_commands),
+                    )
+                    .route(
+                        "/interactions",
+                        web::post().to(slack_interactions::create_slack_interactions),
+                    ),
+            )
+            .service(
+                web::scope("/webhook/github")
+                    .wrap(GithubSignature::new(github_webhook_secret.clone()))
+                    .route("", web::post().to(github_webhook::create_github_events)),
+            )
             .route(
-                "/webhook/slack/events",
-                web::post().to(webhook::create_slack_events),
+                "/files/{team_id}/{file_id}",
+                web::get().to(slack_files::get_slack_file),
             )
             .route("/api/user", web::get().to(api::user::get_user))
             .route("/api/token", web::get().to(api::token::get_token))
             .route("/api/team", web::get().to(api::team::get_team))
+            .route("/api/team", web::put().to(api::team::put_team))
             .route(
                 "/api/teams/{team_id}/reactions",
                 web::get().to(api::reaction::get_reactions),
@@ -86,6 +138,10 @@ pub fn run(
                 "/api/teams/{team_id}/reactions",
                 web::post().to(api::reaction::create_reaction),
             )
+            .route(
+                "/api/reactions/preview",
+                web::post().to(api::reaction::preview_reaction),
+            )
             .route(
                 "/api/reactions/{reaction_id}",
                 web::get().to(api::reaction::get_reaction),
@@ -106,6 +162,23 @@ pub fn run(
                 "/api/reaction_assignees/{reaction_assignee_id}",
                 web::delete().to(api::reaction_assignee::destroy_reaction_assignee),
             )
+            .route(
+                "/api/teams/{team_id}/user_links",
+                web::get().to(api::user_link::get_user_links),
+            )
+            .route(
+                "/api/teams/{team_id}/user_links/{slack_user_id}",
+                web::put().to(api::user_link::put_user_link),
+            )
+            .route(
+                "/api/teams/{team_id}/user_links/{slack_user_id}",
+                web::delete().to(api::user_link::destroy_user_link),
+            )
+            .route("/api/jobs", web::get().to(api::job::get_jobs))
+            .route(
+                "/api/jobs/{job_id}/retry",
+                web::post().to(api::job::retry_job),
+            )
             .route("/api/session", web::delete().to(api::session::delete))
     })
     .listen(listener)?
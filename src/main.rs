@@ -1,14 +1,22 @@
 #![feature(assert_matches)]
 use std::{env, net::TcpListener};
 
-use emoji_to_do::run;
+use emoji_to_do::{run, Services};
 
 use listenfd::ListenFd;
 use sea_orm::Database;
 
+mod clock;
 mod entities;
 mod github;
 mod handlers;
+mod http_client;
+mod issue_template;
+mod jobs;
+mod middleware;
+mod mrkdwn;
+mod secret;
+mod services;
 mod slack;
 mod token;
 
@@ -32,5 +40,5 @@ async fn main() -> std::io::Result<()> {
         Some(listener) => listener,
         None => TcpListener::bind(server_url).expect("failed to bind"),
     };
-    run(listener, connection)?.await
+    run(listener, connection, Services::from_env())?.await
 }
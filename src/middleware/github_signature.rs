@@ -0,0 +1,132 @@
+use std::rc::Rc;
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web::Bytes,
+    HttpResponse,
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use hmac::{Hmac, Mac};
+
+use super::{header_value, read_body, restore_body};
+
+/// Middleware verifying `X-Hub-Signature-256` against the webhook secret of the GitHub App.
+/// https://docs.github.com/en/webhooks/using-webhooks/validating-webhook-deliveries
+pub struct GithubSignature {
+    webhook_secret: Rc<String>,
+}
+
+impl GithubSignature {
+    pub fn new(webhook_secret: String) -> Self {
+        GithubSignature {
+            webhook_secret: Rc::new(webhook_secret),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for GithubSignature
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = GithubSignatureMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(GithubSignatureMiddleware {
+            service: Rc::new(service),
+            webhook_secret: self.webhook_secret.clone(),
+        })
+    }
+}
+
+pub struct GithubSignatureMiddleware<S> {
+    service: Rc<S>,
+    webhook_secret: Rc<String>,
+}
+
+impl<S, B> Service<ServiceRequest> for GithubSignatureMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let webhook_secret = self.webhook_secret.clone();
+
+        Box::pin(async move {
+            let body = read_body(&mut req).await?;
+
+            let signature = header_value(&req, "X-Hub-Signature-256");
+            if !verify(&webhook_secret, &body, &signature) {
+                log::warn!("invalid github signature: {}", req.path());
+                let response = HttpResponse::Unauthorized().finish().map_into_right_body();
+                return Ok(req.into_response(response));
+            }
+
+            restore_body(&mut req, body);
+
+            service
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+fn verify(webhook_secret: &str, body: &Bytes, signature: &str) -> bool {
+    let signature = match signature
+        .strip_prefix("sha256=")
+        .and_then(|s| hex::decode(s).ok())
+    {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let mut mac = match Hmac::<sha2::Sha256>::new_from_slice(webhook_secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Bytes;
+
+    use super::verify;
+
+    // Example from GitHub's documentation
+    const SECRET: &str = "It's a Secret to Everybody";
+    const SIGNATURE: &str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    #[test]
+    fn test_verify() {
+        let body = Bytes::from_static(b"Hello, World!");
+        assert!(verify(SECRET, &body, SIGNATURE));
+
+        // tampered body
+        assert!(!verify(SECRET, &Bytes::from_static(b"Hello"), SIGNATURE));
+        // wrong secret
+        assert!(!verify("secret", &body, SIGNATURE));
+        // malformed header
+        assert!(!verify(SECRET, &body, ""));
+        assert!(!verify(
+            SECRET,
+            &body,
+            "sha1=757107ea0eb2509fc211221cce984b8a"
+        ));
+    }
+}
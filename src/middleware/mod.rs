@@ -0,0 +1,54 @@
+use actix_web::{
+    dev::{self, ServiceRequest},
+    error::PayloadError,
+    http::header,
+    web::{Bytes, BytesMut},
+};
+use futures::{stream, StreamExt};
+
+pub mod github_signature;
+pub mod slack_signature;
+
+// Webhook payloads are far smaller. Bodies are read before their signature is checked, so anyone
+// could send a larger one.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// Signatures are computed over the raw body, so it has to be read before the handler does.
+// A body over `MAX_BODY_SIZE` fails with `PayloadError::Overflow`, which answers 413.
+async fn read_body(req: &mut ServiceRequest) -> Result<Bytes, PayloadError> {
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > MAX_BODY_SIZE) {
+        return Err(PayloadError::Overflow);
+    }
+
+    let (_, payload) = req.parts_mut();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        body.extend_from_slice(&chunk?);
+        if body.len() > MAX_BODY_SIZE {
+            return Err(PayloadError::Overflow);
+        }
+    }
+    Ok(body.freeze())
+}
+
+// Puts the body read by `read_body` back for the handler.
+fn restore_body(req: &mut ServiceRequest, body: Bytes) {
+    let body_stream: dev::Payload =
+        (Box::pin(stream::once(async move { Ok::<_, PayloadError>(body) }))
+            as std::pin::Pin<Box<dyn futures::Stream<Item = _>>>)
+            .into();
+    req.set_payload(body_stream);
+}
+
+fn header_value(req: &ServiceRequest, name: &str) -> String {
+    req.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_owned()
+}
@@ -0,0 +1,184 @@
+use std::{
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web::Bytes,
+    HttpResponse,
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use hmac::{Hmac, Mac};
+
+use super::{header_value, read_body, restore_body};
+
+// Requests older than this are rejected to prevent replay attacks.
+// https://api.slack.com/authentication/verifying-requests-from-slack
+const MAX_TIMESTAMP_SKEW_SECS: u64 = 60 * 5;
+
+/// Middleware verifying `X-Slack-Signature` against the app's signing secret.
+pub struct SlackSignature {
+    signing_secret: Rc<String>,
+}
+
+impl SlackSignature {
+    pub fn new(signing_secret: String) -> Self {
+        SlackSignature {
+            signing_secret: Rc::new(signing_secret),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SlackSignature
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = SlackSignatureMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SlackSignatureMiddleware {
+            service: Rc::new(service),
+            signing_secret: self.signing_secret.clone(),
+        })
+    }
+}
+
+pub struct SlackSignatureMiddleware<S> {
+    service: Rc<S>,
+    signing_secret: Rc<String>,
+}
+
+impl<S, B> Service<ServiceRequest> for SlackSignatureMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let signing_secret = self.signing_secret.clone();
+
+        Box::pin(async move {
+            let body = read_body(&mut req).await?;
+
+            let timestamp = header_value(&req, "X-Slack-Request-Timestamp");
+            let signature = header_value(&req, "X-Slack-Signature");
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+
+            if !verify(&signing_secret, &timestamp, &body, &signature, now) {
+                log::warn!("invalid slack signature: {}", req.path());
+                let response = HttpResponse::Unauthorized().finish().map_into_right_body();
+                return Ok(req.into_response(response));
+            }
+
+            restore_body(&mut req, body);
+
+            service
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+fn verify(signing_secret: &str, timestamp: &str, body: &Bytes, signature: &str, now: u64) -> bool {
+    let timestamp_secs = match timestamp.parse::<u64>() {
+        Ok(t) => t,
+        Err(_) => return false,
+    };
+    if now.abs_diff(timestamp_secs) > MAX_TIMESTAMP_SKEW_SECS {
+        return false;
+    }
+
+    let signature = match signature
+        .strip_prefix("v0=")
+        .and_then(|s| hex::decode(s).ok())
+    {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let mut mac = match Hmac::<sha2::Sha256>::new_from_slice(signing_secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(format!("v0:{}:", timestamp).as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Bytes;
+    use hmac::{Hmac, Mac};
+
+    use super::verify;
+
+    const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+
+    fn sign(timestamp: &str, body: &str) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(format!("v0:{}:{}", timestamp, body).as_bytes());
+        format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify() {
+        let body = Bytes::from_static(b"{\"type\":\"event_callback\"}");
+        let signature = sign("1531420618", "{\"type\":\"event_callback\"}");
+
+        assert!(verify(SECRET, "1531420618", &body, &signature, 1531420618));
+        assert!(verify(
+            SECRET,
+            "1531420618",
+            &body,
+            &signature,
+            1531420618 + 60
+        ));
+
+        // stale timestamp
+        assert!(!verify(
+            SECRET,
+            "1531420618",
+            &body,
+            &signature,
+            1531420618 + 60 * 10
+        ));
+        // tampered body
+        let tampered = Bytes::from_static(b"{\"type\":\"url_verification\"}");
+        assert!(!verify(
+            SECRET,
+            "1531420618",
+            &tampered,
+            &signature,
+            1531420618
+        ));
+        // wrong secret
+        assert!(!verify(
+            "secret",
+            "1531420618",
+            &body,
+            &signature,
+            1531420618
+        ));
+        // malformed headers
+        assert!(!verify(SECRET, "", &body, &signature, 1531420618));
+        assert!(!verify(SECRET, "1531420618", &body, "", 1531420618));
+        assert!(!verify(SECRET, "1531420618", &body, "v1=abcd", 1531420618));
+    }
+}
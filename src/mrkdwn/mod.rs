@@ -0,0 +1,643 @@
+//! Conversion of Slack's mrkdwn into GitHub Flavored Markdown.
+//! https://api.slack.com/reference/surfaces/formatting
+//! https://github.github.com/gfm/
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, TimeZone, Utc};
+use regex::{Captures, Regex};
+
+const FENCE: &str = "```";
+// Slack indents nested list items by 4 spaces
+const LIST_INDENT_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// A single line of plain text, for issue titles
+    Title,
+    /// Multiline Markdown, for issue bodies and comments
+    Body,
+}
+
+/// Names shown in place of the ids in mentions.
+#[derive(Debug, Default)]
+pub struct Names {
+    /// Slack user id to name
+    pub users: HashMap<String, String>,
+    /// Slack user id to linked GitHub login, which takes precedence over the name
+    pub github_logins: HashMap<String, String>,
+    /// Channel id to name
+    pub channels: HashMap<String, String>,
+    /// User group id to handle
+    pub usergroups: HashMap<String, String>,
+}
+
+/// Ids referenced by mentions, which have to be looked up to fill `Names`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Mentions {
+    pub users: Vec<String>,
+    pub channels: Vec<String>,
+    pub usergroups: Vec<String>,
+}
+
+pub fn mentions<'a>(texts: impl IntoIterator<Item = &'a str>) -> Mentions {
+    let re = Regex::new(r"<(@|#|!subteam\^)([A-Z0-9]+)[|>]").unwrap();
+    let mut mentions = Mentions::default();
+    for text in texts {
+        for caps in re.captures_iter(text) {
+            let ids = match &caps[1] {
+                "@" => &mut mentions.users,
+                "#" => &mut mentions.channels,
+                _ => &mut mentions.usergroups,
+            };
+            if !ids.iter().any(|id| id == &caps[2]) {
+                ids.push(caps[2].to_string());
+            }
+        }
+    }
+    mentions
+}
+
+pub fn convert(text: &str, mode: Mode, names: &Names) -> String {
+    match mode {
+        Mode::Title => convert_title(text, names),
+        Mode::Body => convert_body(text, names),
+    }
+}
+
+// GitHub shows titles as they are, except for inline code.
+fn convert_title(text: &str, names: &Names) -> String {
+    let text = text.replace(FENCE, " ");
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let (_, line) = strip_quote(line);
+            convert_inline(&line.chars().collect::<Vec<_>>(), Mode::Title, names)
+        })
+        .collect();
+
+    unescape(&lines.join(" "))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn convert_body(text: &str, names: &Names) -> String {
+    let mut blocks: Vec<String> = vec![];
+    // `>>>` quotes everything after it
+    let mut quote_rest = false;
+
+    let segments = split_fences(text);
+    let last = segments.len() - 1;
+    for (index, segment) in segments.into_iter().enumerate() {
+        match segment {
+            Segment::Code(code) => {
+                let block = format!(
+                    "{}\n{}\n{}",
+                    FENCE,
+                    unescape(code.trim_matches('\n')),
+                    FENCE
+                );
+                blocks.push(if quote_rest { quote(&block) } else { block });
+            }
+            Segment::Text(text) => {
+                // Fences are put on their own lines, so the newlines around them are not needed
+                let mut text = text;
+                if index > 0 {
+                    text = text.strip_prefix('\n').unwrap_or(text);
+                }
+                if index < last {
+                    text = text.strip_suffix('\n').unwrap_or(text);
+                }
+                if !text.is_empty() {
+                    blocks.push(convert_lines(text, &mut quote_rest, names));
+                }
+            }
+        }
+    }
+
+    blocks.join("\n")
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Code(&'a str),
+}
+
+// An unclosed fence is left as text, like Slack does.
+fn split_fences(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find(FENCE) {
+        let after = &rest[start + FENCE.len()..];
+        match after.find(FENCE) {
+            Some(end) => {
+                segments.push(Segment::Text(&rest[..start]));
+                segments.push(Segment::Code(&after[..end]));
+                rest = &after[end + FENCE.len()..];
+            }
+            None => break,
+        }
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+#[derive(PartialEq)]
+enum LineKind {
+    Blank,
+    Quote,
+    ListItem,
+    Paragraph,
+}
+
+fn convert_lines(text: &str, quote_rest: &mut bool, names: &Names) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut previous = LineKind::Blank;
+
+    for line in text.split('\n') {
+        let (quoted, line) = match line.strip_prefix("&gt;&gt;&gt;") {
+            Some(rest) => {
+                *quote_rest = true;
+                (true, rest.strip_prefix(' ').unwrap_or(rest))
+            }
+            None => {
+                let (quoted, line) = strip_quote(line);
+                (quoted || *quote_rest, line)
+            }
+        };
+
+        let (kind, converted) = if quoted {
+            (LineKind::Quote, quote(&convert_line(line, names).1))
+        } else {
+            convert_line(line, names)
+        };
+
+        // A line right after a quote or a list item would continue it
+        if kind == LineKind::Paragraph
+            && (previous == LineKind::Quote || previous == LineKind::ListItem)
+        {
+            lines.push("".to_string());
+        }
+        lines.push(converted);
+        previous = kind;
+    }
+
+    lines.join("\n")
+}
+
+fn convert_line(line: &str, names: &Names) -> (LineKind, String) {
+    if line.trim().is_empty() {
+        return (LineKind::Blank, "".to_string());
+    }
+
+    let list_item = Regex::new(r"^(\s*)([•◦▪▫‣*-]|\d+[.)])\s+(.*)$").unwrap();
+    if let Some(caps) = list_item.captures(line) {
+        let width: usize = caps[1]
+            .chars()
+            .map(|c| if c == '\t' { LIST_INDENT_WIDTH } else { 1 })
+            .sum();
+        let indent = " ".repeat(width / LIST_INDENT_WIDTH * LIST_INDENT_WIDTH);
+        let marker = &caps[2];
+        let marker = match marker.strip_suffix(|c| c == '.' || c == ')') {
+            Some(number) if !number.is_empty() => format!("{}.", number),
+            _ => "-".to_string(),
+        };
+        let content = convert_inline(&caps[3].chars().collect::<Vec<_>>(), Mode::Body, names);
+        return (
+            LineKind::ListItem,
+            format!("{}{} {}", indent, marker, content),
+        );
+    }
+
+    let mut converted = convert_inline(&line.chars().collect::<Vec<_>>(), Mode::Body, names);
+    // Headings and thematic breaks are not a thing in Slack
+    let trimmed = line.trim();
+    if trimmed.starts_with('#') || trimmed.chars().all(|c| "-=_*".contains(c) || c == ' ') {
+        let start = converted.len() - converted.trim_start().len();
+        converted.insert(start, '\\');
+    }
+    (LineKind::Paragraph, converted)
+}
+
+fn quote(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Slack escapes `>` in message text, so quotes start with `&gt;`.
+fn strip_quote(line: &str) -> (bool, &str) {
+    match line.strip_prefix("&gt;").or_else(|| line.strip_prefix('>')) {
+        Some(rest) => (true, rest.strip_prefix(' ').unwrap_or(rest)),
+        None => (false, line),
+    }
+}
+
+fn convert_inline(chars: &[char], mode: Mode, names: &Names) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '<' => {
+                if let Some(end) = find(chars, i + 1, '>') {
+                    let inner: String = chars[i + 1..end].iter().collect();
+                    result.push_str(&convert_angle_brackets(&inner, mode, names));
+                    i = end + 1;
+                    continue;
+                }
+            }
+            '`' => {
+                if let Some(end) = find(chars, i + 1, '`').filter(|end| *end > i + 1) {
+                    let code: String = chars[i + 1..end].iter().collect();
+                    result.push_str(&format!("`{}`", convert_code(&code, names)));
+                    i = end + 1;
+                    continue;
+                }
+            }
+            // Typed `@login` and `#123` would notify people and reference issues on GitHub
+            '@' | '#' if mode == Mode::Body => {
+                if let Some(end) = find_reference_end(chars, i) {
+                    let reference: String = chars[i..end].iter().collect();
+                    result.push_str(&format!("`{}`", reference));
+                    i = end;
+                    continue;
+                }
+            }
+            '*' | '_' | '~' if opens_emphasis(chars, i) => {
+                if let Some(end) = find_closing_emphasis(chars, i) {
+                    let inner = convert_inline(&chars[i + 1..end], mode, names);
+                    let marker = match (mode, c) {
+                        (Mode::Title, _) => "",
+                        (Mode::Body, '*') => "**",
+                        (Mode::Body, '~') => "~~",
+                        (Mode::Body, _) => "_",
+                    };
+                    result.push_str(&format!("{}{}{}", marker, inner, marker));
+                    i = end + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        result.push(c);
+        i += 1;
+    }
+
+    result
+}
+
+// Nothing is formatted inside code, and links become plain text.
+fn convert_code(code: &str, names: &Names) -> String {
+    let re = Regex::new(r"<([^>]*)>").unwrap();
+    let code = re.replace_all(code, |caps: &Captures| {
+        convert_angle_brackets(&caps[1], Mode::Title, names)
+    });
+    unescape(&code)
+}
+
+fn find(chars: &[char], from: usize, target: char) -> Option<usize> {
+    chars[from..]
+        .iter()
+        .position(|c| *c == target)
+        .map(|position| from + position)
+}
+
+// `@login`, `@org/team` and `#123`, as GitHub links them. Addresses like `a@example.com` aren't.
+fn find_reference_end(chars: &[char], start: usize) -> Option<usize> {
+    let is_login_char = |c: &char| c.is_ascii_alphanumeric() || *c == '-';
+    let count =
+        |from: usize, f: &dyn Fn(&char) -> bool| chars[from..].iter().take_while(|c| f(c)).count();
+
+    let end = if chars[start] == '@' {
+        if start > 0 && chars[start - 1].is_alphanumeric() {
+            return None;
+        }
+        let mut end = start + 1 + count(start + 1, &is_login_char);
+        if chars.get(end) == Some(&'/') {
+            let team = count(end + 1, &|c| is_login_char(c) || *c == '_');
+            if team > 0 {
+                end += 1 + team;
+            }
+        }
+        end
+    } else {
+        start + 1 + count(start + 1, &|c| c.is_ascii_digit())
+    };
+
+    let ends_word = chars.get(end).is_none_or(|c| !c.is_alphanumeric());
+    (end > start + 1 && ends_word).then_some(end)
+}
+
+// Slack formats `*bold*` only at word boundaries, and not around spaces.
+fn opens_emphasis(chars: &[char], i: usize) -> bool {
+    let after_boundary = i == 0 || !chars[i - 1].is_alphanumeric();
+    let before_text = chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+    after_boundary && before_text
+}
+
+fn find_closing_emphasis(chars: &[char], start: usize) -> Option<usize> {
+    let marker = chars[start];
+    (start + 2..chars.len()).find(|&j| {
+        chars[j] == marker
+            && !chars[j - 1].is_whitespace()
+            && chars.get(j + 1).is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+// `<@U1234>`, `<#C1234|general>`, `<!here>` and `<https://example.com|label>`
+fn convert_angle_brackets(inner: &str, mode: Mode, names: &Names) -> String {
+    let (target, label) = match inner.split_once('|') {
+        Some((target, label)) => (target, Some(label)),
+        None => (inner, None),
+    };
+
+    if let Some(user) = target.strip_prefix('@') {
+        if let Some(login) = names.github_logins.get(user) {
+            return format!("@{}", login);
+        }
+        let name = names.users.get(user).map(String::as_str);
+        let name = name.or(label).unwrap_or(user);
+        // Slack names may belong to someone else on GitHub, so they are kept from mentioning
+        return match mode {
+            Mode::Title => format!("@{}", name),
+            Mode::Body => format!("`@{}`", name),
+        };
+    }
+    if let Some(channel) = target.strip_prefix('#') {
+        let name = names.channels.get(channel).map(String::as_str);
+        return format!("#{}", name.or(label).unwrap_or(channel));
+    }
+    if let Some(usergroup) = target.strip_prefix("!subteam^") {
+        if let Some(handle) = names.usergroups.get(usergroup) {
+            return format!("@{}", handle);
+        }
+    }
+    if let Some(date) = target.strip_prefix("!date^") {
+        if let Some(formatted) = format_date(date) {
+            return formatted;
+        }
+    }
+    if let Some(special) = target.strip_prefix('!') {
+        return match label {
+            Some(label) => label.to_string(),
+            None => format!("@{}", special),
+        };
+    }
+
+    match (mode, label) {
+        (Mode::Body, Some(label)) => format!(
+            "[{}]({})",
+            label.replace('[', "\\[").replace(']', "\\]"),
+            target.replace(')', "%29")
+        ),
+        (Mode::Title, Some(label)) => label.to_string(),
+        (_, None) => target.to_string(),
+    }
+}
+
+// `<!date^timestamp^token_string^optional_link|fallback_text>`
+// Slack shows dates in the reader's time zone, which an issue can't, so they are written in UTC.
+// Relative tokens like `{date_pretty}` would go stale, so they are written as absolute dates.
+fn format_date(date: &str) -> Option<String> {
+    let mut parts = date.splitn(3, '^');
+    let timestamp = parts.next()?.parse::<i64>().ok()?;
+    let tokens = parts.next()?;
+    let time = Utc.timestamp_opt(timestamp, 0).single()?;
+
+    let day = format!("{}{}", time.day(), ordinal_suffix(time.day()));
+    let date = format!("{} {}, {}", time.format("%B"), day, time.year());
+    let re = Regex::new(r"\{(\w+)\}").unwrap();
+    let mut known = true;
+    let formatted = re.replace_all(tokens, |caps: &Captures| match &caps[1] {
+        "date_num" => time.format("%Y-%m-%d").to_string(),
+        "date" | "date_pretty" => date.clone(),
+        "date_short" | "date_short_pretty" => time.format("%b %-d, %Y").to_string(),
+        "date_long" | "date_long_pretty" => format!("{}, {}", time.format("%A"), date),
+        "time" => time.format("%-I:%M %p UTC").to_string(),
+        "time_secs" => time.format("%-I:%M:%S %p UTC").to_string(),
+        "ago" => time.format("%b %-d, %Y %-I:%M %p UTC").to_string(),
+        _ => {
+            known = false;
+            String::new()
+        }
+    });
+    let formatted = formatted.into_owned();
+
+    known.then_some(formatted)
+}
+
+fn ordinal_suffix(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert, mentions, Mentions, Mode, Names};
+
+    fn names() -> Names {
+        let mut names = Names::default();
+        names.users.insert("U1234".to_string(), "uiur".to_string());
+        names.users.insert("U5678".to_string(), "alice".to_string());
+        names
+            .github_logins
+            .insert("U5678".to_string(), "alice-dev".to_string());
+        names
+            .channels
+            .insert("C0123ABC".to_string(), "dev".to_string());
+        names
+            .usergroups
+            .insert("S0123ABC".to_string(), "backend".to_string());
+        names
+    }
+
+    #[test]
+    fn test_convert_body() {
+        let cases = [
+            ("plain text", "plain text"),
+            ("", ""),
+            // Emphasis
+            ("*bold*", "**bold**"),
+            ("_italic_", "_italic_"),
+            ("~strike~", "~~strike~~"),
+            ("a *bold* and _italic_ word", "a **bold** and _italic_ word"),
+            ("*_bold italic_*", "**_bold italic_**"),
+            ("(*bold*)", "(**bold**)"),
+            ("2 * 3 * 4", "2 * 3 * 4"),
+            ("snake_case_name", "snake_case_name"),
+            ("* not bold*", "- not bold*"),
+            ("*not bold *", "*not bold *"),
+            ("**", "\\**"),
+            // Code
+            ("`code`", "`code`"),
+            ("`*not bold*`", "`*not bold*`"),
+            ("`a &lt; b &amp;&amp; c`", "`a < b && c`"),
+            ("`<https://example.com|label>`", "`label`"),
+            ("``", "``"),
+            ("```let a = 1;```", "```\nlet a = 1;\n```"),
+            (
+                "before\n```\nfn main() {\n    *x* &lt; 1\n}\n```\nafter",
+                "before\n```\nfn main() {\n    *x* < 1\n}\n```\nafter",
+            ),
+            ("see:```a```b", "see:\n```\na\n```\nb"),
+            ("```unclosed", "```unclosed"),
+            // Quotes
+            ("&gt; quoted", "> quoted"),
+            ("&gt;quoted", "> quoted"),
+            ("&gt; *bold* quote", "> **bold** quote"),
+            ("&gt; quoted\nnot quoted", "> quoted\n\nnot quoted"),
+            ("&gt; one\n&gt;\n&gt; two", "> one\n>\n> two"),
+            ("&gt;&gt;&gt; all\nof\n\nthis", "> all\n> of\n>\n> this"),
+            (
+                "&gt;&gt;&gt; quoted\n```code```",
+                "> quoted\n> ```\n> code\n> ```",
+            ),
+            ("a &gt; b", "a &gt; b"),
+            // Lists
+            ("• one\n• two", "- one\n- two"),
+            ("• one\n    ◦ nested", "- one\n    - nested"),
+            ("- one\n- two", "- one\n- two"),
+            ("1. one\n2) two", "1. one\n2. two"),
+            ("• *bold* item", "- **bold** item"),
+            ("intro\n• item\nafter", "intro\n- item\n\nafter"),
+            // Links and mentions
+            ("<https://example.com>", "https://example.com"),
+            (
+                "<https://example.com|example>",
+                "[example](https://example.com)",
+            ),
+            (
+                "<https://example.com/a_(b)|[1]>",
+                "[\\[1\\]](https://example.com/a_(b%29)",
+            ),
+            (
+                "<mailto:a@example.com|a@example.com>",
+                "[a@example.com](mailto:a@example.com)",
+            ),
+            (
+                "*<https://example.com|bold link>*",
+                "**[bold link](https://example.com)**",
+            ),
+            (
+                "<@U1234> foo bar <https://github.com/uiur/sandbox/issues/1>",
+                "`@uiur` foo bar https://github.com/uiur/sandbox/issues/1",
+            ),
+            ("<@U5678> please look", "@alice-dev please look"),
+            ("<@U9999>", "`@U9999`"),
+            ("@uiur please look", "`@uiur` please look"),
+            ("cc @uiur/core.", "cc `@uiur/core`."),
+            ("mail a@example.com", "mail a@example.com"),
+            (
+                "fixed in #12, see uiur/sandbox#3",
+                "fixed in `#12`, see uiur/sandbox`#3`",
+            ),
+            ("see #general and #1st", "see #general and #1st"),
+            ("`@uiur #12`", "`@uiur #12`"),
+            ("<@U9999|someone>", "`@someone`"),
+            ("<!here>", "@here"),
+            ("<!subteam^SAZ94GDB8>", "@subteam^SAZ94GDB8"),
+            ("<!subteam^SAZ94GDB8|@design>", "@design"),
+            ("<#C024BE7LR>", "#C024BE7LR"),
+            ("<#C024BE7LR|general>", "#general"),
+            ("<#C0123ABC>", "#dev"),
+            ("<#C0123ABC|old-name>", "#dev"),
+            ("<!subteam^S0123ABC>", "@backend"),
+            ("<!subteam^S0123ABC|@old-name>", "@backend"),
+            // Dates
+            (
+                "<!date^1392734382^{date_num} {time_secs}|Feb 18, 2014>",
+                "2014-02-18 2:39:42 PM UTC",
+            ),
+            (
+                "<!date^1392734382^{date} at {time}|Feb 18, 2014>",
+                "February 18th, 2014 at 2:39 PM UTC",
+            ),
+            (
+                "<!date^1392734382^{date_long_pretty}^https://example.com|Feb 18, 2014>",
+                "Tuesday, February 18th, 2014",
+            ),
+            ("<!date^1392734382^{date_short}|Feb 18>", "Feb 18, 2014"),
+            ("<!date^1392734382^{unknown}|Feb 18, 2014>", "Feb 18, 2014"),
+            ("<!date^not-a-time^{date}|sometime>", "sometime"),
+            // Markdown that Slack shows as is
+            ("# not a heading", "\\# not a heading"),
+            ("title\n---", "title\n\\---"),
+            ("a &lt;b&gt; tag", "a &lt;b&gt; tag"),
+            ("line\nbreaks\n\nkept", "line\nbreaks\n\nkept"),
+        ];
+
+        let names = names();
+        for (text, expected) in cases {
+            assert_eq!(convert(text, Mode::Body, &names), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_convert_title() {
+        let cases = [
+            ("plain text", "plain text"),
+            ("*bold* _italic_ ~strike~", "bold italic strike"),
+            ("`code` stays", "`code` stays"),
+            ("multiple\nlines\n\njoined", "multiple lines joined"),
+            ("  extra   spaces ", "extra spaces"),
+            ("```\nfenced\n```", "fenced"),
+            ("&gt; quoted", "quoted"),
+            ("a &lt; b &amp;&amp; c &gt; d", "a < b && c > d"),
+            ("<https://example.com|example> link", "example link"),
+            ("<https://example.com>", "https://example.com"),
+            ("<@U1234> reported", "@uiur reported"),
+            ("<#C024BE7LR|general>", "#general"),
+            ("<!channel> deploy", "@channel deploy"),
+            ("<!subteam^S0123ABC> please look", "@backend please look"),
+            (
+                "due <!date^1392734382^{date_short}|Feb 18>",
+                "due Feb 18, 2014",
+            ),
+        ];
+
+        let names = names();
+        for (text, expected) in cases {
+            assert_eq!(convert(text, Mode::Title, &names), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_mentions() {
+        let texts = [
+            "<@U1234> and <@U5678|someone> in <#C0123ABC>",
+            "<!subteam^S0123ABC|@backend> <@U1234> <!here> <https://example.com>",
+        ];
+
+        assert_eq!(
+            mentions(texts),
+            Mentions {
+                users: vec!["U1234".to_string(), "U5678".to_string()],
+                channels: vec!["C0123ABC".to_string()],
+                usergroups: vec!["S0123ABC".to_string()],
+            }
+        );
+        assert_eq!(mentions(["no mentions"]), Mentions::default());
+    }
+}
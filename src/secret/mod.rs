@@ -0,0 +1,102 @@
+use openssl::{
+    base64,
+    error::ErrorStack,
+    rand::rand_bytes,
+    sha::sha256,
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+};
+
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug)]
+pub enum SecretError {
+    InvalidFormat,
+    CipherError(ErrorStack),
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::InvalidFormat => write!(f, "invalid encrypted secret"),
+            SecretError::CipherError(e) => write!(f, "failed to decrypt secret: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+impl From<ErrorStack> for SecretError {
+    fn from(e: ErrorStack) -> Self {
+        SecretError::CipherError(e)
+    }
+}
+
+fn key() -> [u8; 32] {
+    let master_key = std::env::var("MASTER_KEY").expect("MASTER_KEY is expected");
+    sha256(master_key.as_bytes())
+}
+
+/// Encrypts `plaintext` with AES-256-GCM keyed by `MASTER_KEY`.
+/// The result is base64 of iv, tag and ciphertext concatenated.
+pub fn encrypt(plaintext: &str) -> Result<String, SecretError> {
+    let mut iv = [0u8; IV_LEN];
+    rand_bytes(&mut iv)?;
+
+    let mut tag = [0u8; TAG_LEN];
+    let ciphertext = encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key(),
+        Some(&iv),
+        &[],
+        plaintext.as_bytes(),
+        &mut tag,
+    )?;
+
+    let mut data = Vec::with_capacity(IV_LEN + TAG_LEN + ciphertext.len());
+    data.extend_from_slice(&iv);
+    data.extend_from_slice(&tag);
+    data.extend_from_slice(&ciphertext);
+    Ok(base64::encode_block(&data))
+}
+
+pub fn decrypt(encrypted: &str) -> Result<String, SecretError> {
+    let data = base64::decode_block(encrypted).map_err(|_| SecretError::InvalidFormat)?;
+    if data.len() < IV_LEN + TAG_LEN {
+        return Err(SecretError::InvalidFormat);
+    }
+
+    let (iv, rest) = data.split_at(IV_LEN);
+    let (tag, ciphertext) = rest.split_at(TAG_LEN);
+    let plaintext = decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key(),
+        Some(iv),
+        &[],
+        ciphertext,
+        tag,
+    )?;
+
+    String::from_utf8(plaintext).map_err(|_| SecretError::InvalidFormat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt};
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        std::env::set_var("MASTER_KEY", "master key for test");
+
+        let encrypted = encrypt("xoxb-1234").unwrap();
+        assert_ne!(encrypted, "xoxb-1234");
+        assert_ne!(encrypted, encrypt("xoxb-1234").unwrap());
+        assert_eq!(decrypt(&encrypted).unwrap(), "xoxb-1234");
+
+        let mut tampered = openssl::base64::decode_block(&encrypted).unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt(&openssl::base64::encode_block(&tampered)).is_err());
+        assert!(decrypt("not encrypted").is_err());
+    }
+}
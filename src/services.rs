@@ -0,0 +1,35 @@
+use std::{env, sync::Arc};
+
+use crate::{
+    github::{GithubRestApi, IssueTracker, GITHUB_API_URL},
+    http_client::HttpClient,
+    slack::{SlackApi, SlackWebApi, SLACK_API_URL},
+};
+
+/// The APIs the app calls, shared by the handlers and the job workers.
+#[derive(Clone)]
+pub struct Services {
+    pub http: HttpClient,
+    pub slack_api: Arc<dyn SlackApi>,
+    pub issue_tracker: Arc<dyn IssueTracker>,
+}
+
+impl Services {
+    /// Calls the Slack Web API and the GitHub REST API at the base urls, e.g. a local fake.
+    pub fn with_base_urls(slack_api_url: &str, github_api_url: &str) -> Self {
+        let http = HttpClient::new();
+        Services {
+            slack_api: Arc::new(SlackWebApi::new(http.clone(), slack_api_url)),
+            issue_tracker: Arc::new(GithubRestApi::new(http.clone(), github_api_url)),
+            http,
+        }
+    }
+
+    /// Calls Slack and GitHub, unless `SLACK_API_URL` or `GITHUB_API_URL` points elsewhere.
+    pub fn from_env() -> Self {
+        let slack_api_url = env::var("SLACK_API_URL").unwrap_or_else(|_| SLACK_API_URL.to_owned());
+        let github_api_url =
+            env::var("GITHUB_API_URL").unwrap_or_else(|_| GITHUB_API_URL.to_owned());
+        Services::with_base_urls(&slack_api_url, &github_api_url)
+    }
+}
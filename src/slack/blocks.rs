@@ -0,0 +1,342 @@
+//! Block Kit layouts posted by the app.
+//! https://api.slack.com/block-kit
+
+use serde_json::{json, Value};
+
+use super::escape;
+
+pub const ASSIGN_TO_ME_ACTION_ID: &str = "assign_to_me";
+pub const CLOSE_ISSUE_ACTION_ID: &str = "close_issue";
+pub const CHANGE_REPO_ACTION_ID: &str = "change_repo";
+pub const OPEN_ISSUE_FORM_ACTION_ID: &str = "open_issue_form";
+
+pub const ISSUE_FORM_CALLBACK_ID: &str = "issue_form";
+// Inputs of the issue form. Each is the block id and the action id of its input.
+pub const ISSUE_FORM_TITLE: &str = "title";
+pub const ISSUE_FORM_BODY: &str = "body";
+pub const ISSUE_FORM_REPO: &str = "repo";
+pub const ISSUE_FORM_LABELS: &str = "labels";
+pub const ISSUE_FORM_ASSIGNEES: &str = "assignees";
+
+const ISSUE_CARD_BLOCK_ID_PREFIX: &str = "issue_card:";
+const ISSUE_DRAFT_BLOCK_ID_PREFIX: &str = "issue_draft:";
+// Longest text a plain_text_input takes
+const PLAIN_TEXT_INPUT_MAX_LENGTH: usize = 3000;
+
+/// An issue filed from a Slack message.
+pub struct IssueCard<'a> {
+    /// Id of the message-to-issue link the buttons act on. Cards without it have no buttons.
+    pub message_issue_id: Option<i32>,
+    pub repo: &'a str,
+    pub number: i32,
+    pub title: &'a str,
+    pub html_url: &'a str,
+    pub closed: bool,
+    pub labels: &'a [String],
+    pub assignees: &'a [String],
+    /// Repositories the issue can be moved to
+    pub other_repos: &'a [String],
+}
+
+pub fn issue_card(card: &IssueCard) -> Vec<Value> {
+    let mut fields = vec![
+        format!("*Repository*\n{}", escape(card.repo)),
+        format!("*Status*\n{}", if card.closed { "Closed" } else { "Open" }),
+    ];
+    if !card.labels.is_empty() {
+        fields.push(format!("*Labels*\n{}", escape(&card.labels.join(", "))));
+    }
+    if !card.assignees.is_empty() {
+        let assignees: Vec<String> = card
+            .assignees
+            .iter()
+            .map(|login| format!("@{}", escape(login)))
+            .collect();
+        fields.push(format!("*Assignees*\n{}", assignees.join(", ")));
+    }
+
+    let mut blocks = vec![
+        json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!("<{}|*#{} {}*>", card.html_url, card.number, escape(card.title)),
+            },
+        }),
+        json!({
+            "type": "section",
+            "fields": fields
+                .into_iter()
+                .map(|text| json!({ "type": "mrkdwn", "text": text }))
+                .collect::<Vec<_>>(),
+        }),
+    ];
+
+    let message_issue_id = match card.message_issue_id {
+        Some(message_issue_id) => message_issue_id,
+        None => return blocks,
+    };
+
+    let mut elements = vec![json!({
+        "type": "button",
+        "action_id": ASSIGN_TO_ME_ACTION_ID,
+        "text": { "type": "plain_text", "text": "Assign to me" },
+    })];
+    if !card.closed {
+        elements.push(json!({
+            "type": "button",
+            "action_id": CLOSE_ISSUE_ACTION_ID,
+            "text": { "type": "plain_text", "text": "Close" },
+            "style": "danger",
+        }));
+    }
+    // A select needs at least one option
+    if !card.other_repos.is_empty() {
+        elements.push(json!({
+            "type": "static_select",
+            "action_id": CHANGE_REPO_ACTION_ID,
+            "placeholder": { "type": "plain_text", "text": "Change repo" },
+            "options": card
+                .other_repos
+                .iter()
+                .map(|repo| json!({
+                    "text": { "type": "plain_text", "text": repo },
+                    "value": repo,
+                }))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    blocks.push(json!({
+        "type": "actions",
+        "block_id": format!("{}{}", ISSUE_CARD_BLOCK_ID_PREFIX, message_issue_id),
+        "elements": elements,
+    }));
+
+    blocks
+}
+
+/// Prompt asking the reactioner to review an issue before it is filed.
+pub fn issue_draft_prompt(issue_draft_id: i32, repo: &str, title: &str) -> Vec<Value> {
+    vec![
+        section(&format!("File *{}* to {}?", escape(title), escape(repo))),
+        json!({
+            "type": "actions",
+            "block_id": format!("{}{}", ISSUE_DRAFT_BLOCK_ID_PREFIX, issue_draft_id),
+            "elements": [{
+                "type": "button",
+                "action_id": OPEN_ISSUE_FORM_ACTION_ID,
+                "text": { "type": "plain_text", "text": "Edit and file" },
+                "style": "primary",
+            }],
+        }),
+    ]
+}
+
+/// Returns the draft id of an action on an issue draft prompt.
+pub fn parse_issue_draft_block_id(block_id: &str) -> Option<i32> {
+    block_id
+        .strip_prefix(ISSUE_DRAFT_BLOCK_ID_PREFIX)
+        .and_then(|id| id.parse().ok())
+}
+
+/// Values the issue form is opened with.
+pub struct IssueForm<'a> {
+    pub issue_draft_id: i32,
+    pub repo: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    /// Comma separated
+    pub labels: &'a str,
+    /// Comma separated
+    pub assignees: &'a str,
+}
+
+/// Modal to edit an issue before it is filed. The draft id is carried in `private_metadata`.
+/// https://api.slack.com/surfaces/modals
+pub fn issue_form(form: &IssueForm) -> Value {
+    json!({
+        "type": "modal",
+        "callback_id": ISSUE_FORM_CALLBACK_ID,
+        "private_metadata": form.issue_draft_id.to_string(),
+        "title": { "type": "plain_text", "text": "File an issue" },
+        "submit": { "type": "plain_text", "text": "File" },
+        "close": { "type": "plain_text", "text": "Cancel" },
+        "blocks": [
+            input(ISSUE_FORM_TITLE, "Title", form.title, false, false),
+            input(ISSUE_FORM_BODY, "Body", form.body, true, false),
+            input(ISSUE_FORM_REPO, "Repository", form.repo, false, false),
+            input(ISSUE_FORM_LABELS, "Labels", form.labels, false, true),
+            input(ISSUE_FORM_ASSIGNEES, "Assignees", form.assignees, false, true),
+        ],
+    })
+}
+
+fn input(id: &str, label: &str, value: &str, multiline: bool, optional: bool) -> Value {
+    let mut element = json!({
+        "type": "plain_text_input",
+        "action_id": id,
+        "multiline": multiline,
+    });
+    // Slack rejects the whole view when an initial value is too long
+    let value: String = value.chars().take(PLAIN_TEXT_INPUT_MAX_LENGTH).collect();
+    if !value.is_empty() {
+        element["initial_value"] = json!(value);
+    }
+
+    json!({
+        "type": "input",
+        "block_id": id,
+        "label": { "type": "plain_text", "text": label },
+        "element": element,
+        "optional": optional,
+    })
+}
+
+pub fn section(text: &str) -> Value {
+    json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": text },
+    })
+}
+
+pub fn context(text: &str) -> Value {
+    json!({
+        "type": "context",
+        "elements": [{ "type": "mrkdwn", "text": text }],
+    })
+}
+
+/// Returns the message-to-issue link id of an action on an issue card.
+pub fn parse_issue_card_block_id(block_id: &str) -> Option<i32> {
+    block_id
+        .strip_prefix(ISSUE_CARD_BLOCK_ID_PREFIX)
+        .and_then(|id| id.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        issue_card, issue_draft_prompt, issue_form, parse_issue_card_block_id,
+        parse_issue_draft_block_id, IssueCard, IssueForm,
+    };
+
+    #[test]
+    fn test_issue_card() {
+        let labels = vec!["bug".to_string()];
+        let assignees = vec!["uiur".to_string()];
+        let other_repos = vec!["uiur/bugs".to_string()];
+        let card = IssueCard {
+            message_issue_id: Some(3),
+            repo: "uiur/sandbox",
+            number: 12,
+            title: "build <main> fails",
+            html_url: "https://github.com/uiur/sandbox/issues/12",
+            closed: false,
+            labels: &labels,
+            assignees: &assignees,
+            other_repos: &other_repos,
+        };
+
+        let blocks = issue_card(&card);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(
+            blocks[0]["text"]["text"],
+            "<https://github.com/uiur/sandbox/issues/12|*#12 build &lt;main&gt; fails*>"
+        );
+        assert_eq!(
+            blocks[1]["fields"],
+            json!([
+                { "type": "mrkdwn", "text": "*Repository*\nuiur/sandbox" },
+                { "type": "mrkdwn", "text": "*Status*\nOpen" },
+                { "type": "mrkdwn", "text": "*Labels*\nbug" },
+                { "type": "mrkdwn", "text": "*Assignees*\n@uiur" },
+            ])
+        );
+        assert_eq!(blocks[2]["block_id"], "issue_card:3");
+        let action_ids: Vec<&str> = blocks[2]["elements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|element| element["action_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            action_ids,
+            vec!["assign_to_me", "close_issue", "change_repo"]
+        );
+
+        // Closed issues can't be closed again, and there is nowhere to move the issue to
+        let blocks = issue_card(&IssueCard {
+            closed: true,
+            other_repos: &[],
+            ..card
+        });
+        assert_eq!(blocks[2]["elements"].as_array().unwrap().len(), 1);
+
+        let blocks = issue_card(&IssueCard {
+            message_issue_id: None,
+            ..card
+        });
+        assert_eq!(blocks.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_issue_card_block_id() {
+        assert_eq!(parse_issue_card_block_id("issue_card:3"), Some(3));
+        assert_eq!(parse_issue_card_block_id("issue_card:"), None);
+        assert_eq!(parse_issue_card_block_id("other:3"), None);
+    }
+
+    #[test]
+    fn test_issue_draft_prompt() {
+        let blocks = issue_draft_prompt(5, "uiur/sandbox", "build <main> fails");
+        assert_eq!(
+            blocks[0]["text"]["text"],
+            "File *build &lt;main&gt; fails* to uiur/sandbox?"
+        );
+        assert_eq!(blocks[1]["block_id"], "issue_draft:5");
+        assert_eq!(blocks[1]["elements"][0]["action_id"], "open_issue_form");
+
+        assert_eq!(parse_issue_draft_block_id("issue_draft:5"), Some(5));
+        assert_eq!(parse_issue_draft_block_id("issue_card:5"), None);
+    }
+
+    #[test]
+    fn test_issue_form() {
+        let body = "a".repeat(3001);
+        let view = issue_form(&IssueForm {
+            issue_draft_id: 5,
+            repo: "uiur/sandbox",
+            title: "build fails",
+            body: &body,
+            labels: "bug, ci",
+            assignees: "",
+        });
+
+        assert_eq!(view["callback_id"], "issue_form");
+        assert_eq!(view["private_metadata"], "5");
+        let block_ids: Vec<&str> = view["blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|block| block["block_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            block_ids,
+            vec!["title", "body", "repo", "labels", "assignees"]
+        );
+        assert_eq!(view["blocks"][0]["element"]["initial_value"], "build fails");
+        assert_eq!(
+            view["blocks"][1]["element"]["initial_value"]
+                .as_str()
+                .unwrap()
+                .len(),
+            3000
+        );
+        assert_eq!(view["blocks"][3]["element"]["initial_value"], "bug, ci");
+        assert!(view["blocks"][4]["element"].get("initial_value").is_none());
+        assert_eq!(view["blocks"][4]["optional"], true);
+    }
+}
@@ -0,0 +1,267 @@
+//! Cached lookups of Slack users, channels and user groups.
+//! Every reaction needs the names of the people and channels in the messages, which rarely
+//! change, so they are kept in process for a while instead of being fetched each time.
+//! Entries are keyed by Slack team, and concurrent lookups of the same entry share a request.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Mutex, OnceLock},
+};
+
+use futures::future::{BoxFuture, FutureExt, Shared};
+
+use crate::{
+    clock::unix_now,
+    mrkdwn::{Mentions, Names},
+};
+
+use super::{SlackClient, SlackClientError, SlackUser};
+
+// `user_change` events evict users early, so they can be kept longer than channels.
+const USER_TTL_SECS: i64 = 24 * 60 * 60;
+const CHANNEL_TTL_SECS: i64 = 60 * 60;
+const USERGROUPS_TTL_SECS: i64 = 60 * 60;
+
+// A failed lookup is evicted once it resolves, so it is retried by the next caller.
+type Lookup<T> = Shared<BoxFuture<'static, Result<T, SlackClientError>>>;
+
+struct CacheEntry<T> {
+    lookup: Lookup<T>,
+    expires_at: i64,
+}
+
+struct DirectoryCache<T> {
+    ttl_secs: i64,
+    // (Slack team id, id) to the lookup, which may still be in flight
+    entries: Mutex<HashMap<(String, String), CacheEntry<T>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> DirectoryCache<T> {
+    fn new(ttl_secs: i64) -> Self {
+        DirectoryCache {
+            ttl_secs,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get_or_fetch<F, Fut>(
+        &self,
+        team_id: &str,
+        id: &str,
+        fetch: F,
+    ) -> Result<T, SlackClientError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, SlackClientError>> + Send + 'static,
+    {
+        let key = (team_id.to_string(), id.to_string());
+        let lookup = self.lookup(key.clone(), unix_now(), fetch);
+        let value = lookup.clone().await;
+
+        if value.is_err() {
+            let mut entries = self.entries.lock().unwrap();
+            if entries
+                .get(&key)
+                .is_some_and(|entry| entry.lookup.peek().is_some_and(Result::is_err))
+            {
+                entries.remove(&key);
+            }
+        }
+        value
+    }
+
+    fn lookup<F, Fut>(&self, key: (String, String), now: i64, fetch: F) -> Lookup<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, SlackClientError>> + Send + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.expires_at > now);
+
+        entries
+            .entry(key)
+            .or_insert_with(|| CacheEntry {
+                lookup: fetch().boxed().shared(),
+                expires_at: now + self.ttl_secs,
+            })
+            .lookup
+            .clone()
+    }
+
+    fn invalidate(&self, team_id: &str, id: &str) {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(team_id.to_string(), id.to_string()));
+    }
+}
+
+fn users() -> &'static DirectoryCache<SlackUser> {
+    static USERS: OnceLock<DirectoryCache<SlackUser>> = OnceLock::new();
+    USERS.get_or_init(|| DirectoryCache::new(USER_TTL_SECS))
+}
+
+fn channels() -> &'static DirectoryCache<String> {
+    static CHANNELS: OnceLock<DirectoryCache<String>> = OnceLock::new();
+    CHANNELS.get_or_init(|| DirectoryCache::new(CHANNEL_TTL_SECS))
+}
+
+// usergroups.list returns the handles of the whole team, which are cached as a single entry.
+fn usergroups() -> &'static DirectoryCache<HashMap<String, String>> {
+    static USERGROUPS: OnceLock<DirectoryCache<HashMap<String, String>>> = OnceLock::new();
+    USERGROUPS.get_or_init(|| DirectoryCache::new(USERGROUPS_TTL_SECS))
+}
+
+/// Drops the cached profile of a user, e.g. when they changed their name.
+pub fn invalidate_user(team_id: &str, user: &str) {
+    users().invalidate(team_id, user);
+}
+
+impl SlackClient {
+    pub async fn get_user_info(&self, user: &str) -> Result<SlackUser, SlackClientError> {
+        let client = self.clone();
+        let id = user.to_string();
+        users()
+            .get_or_fetch(&self.team_id, user, || async move {
+                client.api.get_user_info(&client.token, &id).await
+            })
+            .await
+    }
+
+    pub async fn get_channel_name(&self, channel: &str) -> Result<String, SlackClientError> {
+        let client = self.clone();
+        let id = channel.to_string();
+        channels()
+            .get_or_fetch(&self.team_id, channel, || async move {
+                client.api.get_channel_name(&client.token, &id).await
+            })
+            .await
+    }
+
+    /// Returns the handles of the team's user groups by id.
+    pub async fn get_usergroup_handles(&self) -> Result<HashMap<String, String>, SlackClientError> {
+        let client = self.clone();
+        usergroups()
+            .get_or_fetch(&self.team_id, "", || async move {
+                client.api.get_usergroup_handles(&client.token).await
+            })
+            .await
+    }
+
+    /// Looks up the names of mentioned users, channels and user groups.
+    /// Ids that can't be looked up are left out, so the mention falls back to its label.
+    pub async fn resolve_names(&self, mentions: &Mentions, names: &mut Names) {
+        for user in &mentions.users {
+            if names.users.contains_key(user) {
+                continue;
+            }
+            if let Ok(user_info) = self.get_user_info(user).await {
+                names.users.insert(user.clone(), user_info.name);
+            }
+        }
+        for channel in &mentions.channels {
+            if let Ok(name) = self.get_channel_name(channel).await {
+                names.channels.insert(channel.clone(), name);
+            }
+        }
+        if !mentions.usergroups.is_empty() {
+            if let Ok(handles) = self.get_usergroup_handles().await {
+                for usergroup in &mentions.usergroups {
+                    if let Some(handle) = handles.get(usergroup) {
+                        names.usergroups.insert(usergroup.clone(), handle.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::{DirectoryCache, SlackClientError};
+
+    fn not_found() -> SlackClientError {
+        SlackClientError::Api {
+            status: 200,
+            code: "user_not_found".to_string(),
+            body: String::new(),
+        }
+    }
+
+    fn counting_fetch(
+        count: &Arc<AtomicUsize>,
+        value: Option<&str>,
+    ) -> impl FnOnce() -> futures::future::BoxFuture<'static, Result<String, SlackClientError>>
+    {
+        let count = count.clone();
+        let value = value.map(str::to_string).ok_or_else(not_found);
+        move || {
+            Box::pin(async move {
+                count.fetch_add(1, Ordering::SeqCst);
+                actix_rt::time::sleep(std::time::Duration::from_millis(10)).await;
+                value
+            })
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_directory_cache_coalesces_lookups() {
+        let cache = DirectoryCache::new(60);
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = futures::join!(
+            cache.get_or_fetch("T1", "U1", counting_fetch(&count, Some("alice"))),
+            cache.get_or_fetch("T1", "U1", counting_fetch(&count, Some("alice"))),
+        );
+        assert_eq!(a.as_deref().ok(), Some("alice"));
+        assert_eq!(b.as_deref().ok(), Some("alice"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Other teams have their own entries
+        cache
+            .get_or_fetch("T2", "U1", counting_fetch(&count, Some("bob")))
+            .await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        cache.invalidate("T1", "U1");
+        let c = cache
+            .get_or_fetch("T1", "U1", counting_fetch(&count, Some("alicia")))
+            .await;
+        assert_eq!(c.as_deref().ok(), Some("alicia"));
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_directory_cache_evicts_failures_and_expired_entries() {
+        let cache = DirectoryCache::new(60);
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let e = cache
+            .get_or_fetch("T1", "U1", counting_fetch(&count, None))
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), Some("user_not_found"));
+        assert_eq!(
+            cache
+                .get_or_fetch("T1", "U1", counting_fetch(&count, Some("alice")))
+                .await
+                .as_deref()
+                .ok(),
+            Some("alice")
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        let key = ("T1".to_string(), "U1".to_string());
+        let now = crate::clock::unix_now();
+        cache.lookup(key.clone(), now + 59, || async { Err(not_found()) });
+        assert_eq!(cache.entries.lock().unwrap().len(), 1);
+        let lookup = cache.lookup(key, now + 61, counting_fetch(&count, Some("alicia")));
+        assert_eq!(lookup.await.as_deref().ok(), Some("alicia"));
+    }
+}
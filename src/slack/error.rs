@@ -0,0 +1,226 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Error of a Slack API call.
+#[derive(Debug, Clone)]
+pub enum SlackClientError {
+    /// No response came back, e.g. the connection failed or timed out
+    Request { message: String, timeout: bool },
+    /// Slack answered `ok: false` or with an error status. `code` is the Web API error code,
+    /// e.g. `not_in_channel`, or `http_<status>` for urls that aren't Web API methods.
+    Api {
+        status: u16,
+        code: String,
+        body: String,
+    },
+    /// The response isn't what the method returns
+    Json {
+        status: u16,
+        body: String,
+        message: String,
+    },
+}
+
+// Web API error codes, other than those shared by every method
+// https://api.slack.com/web#errors
+const NOT_FOUND_CODES: &[&str] = &[
+    "channel_not_found",
+    "file_not_found",
+    "message_not_found",
+    "thread_not_found",
+    "user_not_found",
+];
+const AUTH_CODES: &[&str] = &[
+    "account_inactive",
+    "invalid_auth",
+    "not_authed",
+    "token_expired",
+    "token_revoked",
+];
+
+impl SlackClientError {
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            SlackClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            SlackClientError::Api { body, .. } | SlackClientError::Json { body, .. } => Some(body),
+            SlackClientError::Request { .. } => None,
+        }
+    }
+
+    fn is_rate_limited(&self) -> bool {
+        matches!(self, SlackClientError::Api { status: 429, .. })
+            || self.code() == Some("ratelimited")
+    }
+
+    /// Whether the same call may succeed later. Other errors need someone to fix something.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SlackClientError::Request { .. } => true,
+            SlackClientError::Api { status, code, .. } => {
+                self.is_rate_limited() || *status >= 500 || code == "internal_error"
+            }
+            SlackClientError::Json { .. } => false,
+        }
+    }
+
+    /// What went wrong and what to do about it, for the user who triggered the call.
+    pub fn user_message(&self) -> String {
+        if self.is_rate_limited() {
+            return "Slack is rate limiting emoji-to-do. Please try again in a minute.".to_string();
+        }
+
+        match self {
+            SlackClientError::Request { .. } => {
+                "emoji-to-do couldn't reach Slack. Please try again later.".to_string()
+            }
+            SlackClientError::Api { code, .. } => match code.as_str() {
+                "not_in_channel" => {
+                    "emoji-to-do isn't in this channel. Invite it with `/invite @emoji-to-do` and try again."
+                        .to_string()
+                }
+                "channel_not_found" => {
+                    "emoji-to-do can't see this channel. Invite it with `/invite @emoji-to-do` and try again."
+                        .to_string()
+                }
+                "is_archived" => "This channel is archived.".to_string(),
+                "missing_scope" => {
+                    "emoji-to-do is missing a permission. Ask an admin to reinstall the app."
+                        .to_string()
+                }
+                code if AUTH_CODES.contains(&code) => {
+                    "emoji-to-do is no longer authorized in this workspace. Ask an admin to reinstall the app."
+                        .to_string()
+                }
+                code => format!("Slack returned an error: `{}`", code),
+            },
+            SlackClientError::Json { .. } => {
+                "Slack returned an unexpected response. Please try again later.".to_string()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SlackClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SlackClientError::Request { message, .. } => {
+                write!(f, "slack request failed: {}", message)
+            }
+            SlackClientError::Api { status, code, .. } => {
+                write!(f, "slack api error: {} ({})", code, status)
+            }
+            SlackClientError::Json {
+                status, message, ..
+            } => write!(f, "slack json parse error: {} ({})", message, status),
+        }
+    }
+}
+
+impl std::error::Error for SlackClientError {}
+
+impl From<reqwest::Error> for SlackClientError {
+    fn from(e: reqwest::Error) -> Self {
+        SlackClientError::Request {
+            message: e.to_string(),
+            timeout: e.is_timeout(),
+        }
+    }
+}
+
+// Slack is upstream of every request, so its errors are gateway errors unless something wasn't
+// found.
+impl ResponseError for SlackClientError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            _ if self.is_rate_limited() => StatusCode::SERVICE_UNAVAILABLE,
+            SlackClientError::Request { timeout: true, .. } => StatusCode::GATEWAY_TIMEOUT,
+            SlackClientError::Api { code, .. } if NOT_FOUND_CODES.contains(&code.as_str()) => {
+                StatusCode::NOT_FOUND
+            }
+            _ => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).body(self.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    ok: bool,
+    error: Option<String>,
+}
+
+/// Parses the body of a Web API method, which answers errors with `ok: false`.
+pub(super) fn parse_response<T: DeserializeOwned>(
+    status: reqwest::StatusCode,
+    body: String,
+) -> Result<T, SlackClientError> {
+    let status = status.as_u16();
+    match serde_json::from_str::<ApiResponse>(&body) {
+        Ok(ApiResponse { ok: false, error }) => Err(SlackClientError::Api {
+            status,
+            code: error.unwrap_or_else(|| "unknown_error".to_string()),
+            body,
+        }),
+        _ if !(200..300).contains(&status) => Err(SlackClientError::Api {
+            status,
+            code: format!("http_{}", status),
+            body,
+        }),
+        _ => serde_json::from_str(&body).map_err(|e| SlackClientError::Json {
+            status,
+            message: e.to_string(),
+            body,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, ResponseError};
+
+    use super::{parse_response, SlackClientError};
+
+    fn parse(status: u16, body: &str) -> Result<serde_json::Value, SlackClientError> {
+        parse_response(
+            reqwest::StatusCode::from_u16(status).unwrap(),
+            body.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse(200, r#"{"ok":true,"ts":"1.0"}"#).unwrap()["ts"],
+            "1.0"
+        );
+
+        let e = parse(200, r#"{"ok":false,"error":"not_in_channel"}"#).unwrap_err();
+        assert_eq!(e.code(), Some("not_in_channel"));
+        assert!(!e.is_retryable());
+        assert_eq!(e.status_code(), StatusCode::BAD_GATEWAY);
+        assert!(e.user_message().contains("/invite @emoji-to-do"));
+
+        let e = parse(429, r#"{"ok":false,"error":"ratelimited"}"#).unwrap_err();
+        assert!(e.is_retryable());
+        assert_eq!(e.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let e = parse(200, r#"{"ok":false,"error":"file_not_found"}"#).unwrap_err();
+        assert_eq!(e.status_code(), StatusCode::NOT_FOUND);
+
+        let e = parse(502, "<html>Bad Gateway</html>").unwrap_err();
+        assert_eq!(e.code(), Some("http_502"));
+        assert!(e.is_retryable());
+
+        let e = parse(200, "not json").unwrap_err();
+        assert!(matches!(e, SlackClientError::Json { status: 200, .. }));
+    }
+}
@@ -1,7 +1,15 @@
-use std::{collections::HashMap, env};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
-use log::error;
-use serde::Deserialize;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+pub mod blocks;
+pub mod directory;
+mod error;
+mod web_api;
+
+pub use error::SlackClientError;
+pub use web_api::{SlackWebApi, SLACK_API_URL};
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -12,7 +20,11 @@ pub enum SlackRequest {
         challenge: String,
     },
 
+    // https://api.slack.com/types/event
     EventCallback {
+        event_id: String,
+        team_id: String,
+        event_time: i64,
         event: SlackEvent,
     },
 
@@ -30,10 +42,43 @@ pub enum SlackEvent {
         reaction: String,
         item: SlackItem,
     },
+    // https://api.slack.com/events/app_mention
     AppMention {
         user: String,
         text: String,
         channel: String,
+        ts: String,
+        thread_ts: Option<String>,
+    },
+    // https://api.slack.com/events/user_change
+    UserChange {
+        user: IdRef,
+    },
+
+    #[serde(other)]
+    Other,
+}
+
+// https://api.slack.com/reference/interaction-payloads
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "type")]
+pub enum SlackInteraction {
+    // https://api.slack.com/reference/interaction-payloads/block-actions
+    BlockActions {
+        team: IdRef,
+        user: IdRef,
+        channel: Option<IdRef>,
+        container: InteractionContainer,
+        response_url: Option<String>,
+        /// Opens a modal within 3 seconds of the interaction
+        trigger_id: Option<String>,
+        actions: Vec<BlockAction>,
+    },
+    // https://api.slack.com/reference/interaction-payloads/views#view_submission
+    ViewSubmission {
+        team: IdRef,
+        view: View,
     },
 
     #[serde(other)]
@@ -41,6 +86,60 @@ pub enum SlackEvent {
 }
 
 #[derive(Deserialize, Debug)]
+pub struct IdRef {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct InteractionContainer {
+    pub message_ts: Option<String>,
+    #[serde(default)]
+    pub is_ephemeral: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlockAction {
+    pub action_id: String,
+    pub block_id: String,
+    pub selected_option: Option<SelectedOption>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SelectedOption {
+    pub value: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct View {
+    pub callback_id: String,
+    #[serde(default)]
+    pub private_metadata: String,
+    pub state: ViewState,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ViewState {
+    /// Input values keyed by block id, then by action id
+    pub values: HashMap<String, HashMap<String, ViewStateValue>>,
+}
+
+impl ViewState {
+    /// Returns the value of a plain text input, or "" when it is left empty.
+    pub fn value(&self, block_id: &str, action_id: &str) -> &str {
+        self.values
+            .get(block_id)
+            .and_then(|actions| actions.get(action_id))
+            .and_then(|value| value.value.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ViewStateValue {
+    pub value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "type")]
 pub enum SlackItem {
@@ -53,30 +152,43 @@ pub enum SlackItem {
     Other,
 }
 
-pub async fn post_message(channel: &str, text: &str) -> Result<(), ()> {
-    let client = reqwest::Client::new();
-    let token = env::var("SLACK_TOKEN").unwrap_or_default();
-
-    let mut data = HashMap::new();
-    data.insert("channel", channel);
-    data.insert("text", text);
-
-    let _resp = client
-        .post("https://slack.com/api/chat.postMessage")
-        .header("Content-Type", "application/json")
-        .bearer_auth(token)
-        .json(&data)
-        .send()
-        .await
-        .map_err(|_e| ())?;
+/// How the app tells the reactioner about the issue it filed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyMode {
+    /// In the thread of the reacted message
+    #[default]
+    Thread,
+    /// Only visible to the reactioner, in the thread of the reacted message
+    Ephemeral,
+    /// In a direct message to the reactioner
+    Dm,
+    Silent,
+}
 
-    Ok(())
+impl ReplyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplyMode::Thread => "thread",
+            ReplyMode::Ephemeral => "ephemeral",
+            ReplyMode::Dm => "dm",
+            ReplyMode::Silent => "silent",
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct ConversationsHistoryResponse {
-    ok: bool,
-    messages: Vec<SlackMessage>,
+impl FromStr for ReplyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "thread" => Ok(ReplyMode::Thread),
+            "ephemeral" => Ok(ReplyMode::Ephemeral),
+            "dm" => Ok(ReplyMode::Dm),
+            "silent" => Ok(ReplyMode::Silent),
+            _ => Err(format!("unknown reply mode: {}", s)),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -84,107 +196,331 @@ pub struct SlackMessage {
     pub user: String,
     pub text: String,
     pub ts: String,
+    // Set on both the parent and the replies of a thread
+    pub thread_ts: Option<String>,
+    #[serde(default)]
+    pub files: Vec<SlackFile>,
 }
 
-pub async fn get_messages(channel: &str, ts: &str, count: u32) -> Result<Vec<SlackMessage>, ()> {
-    let client = reqwest::Client::new();
-    let token = env::var("SLACK_TOKEN").unwrap_or_default();
-
-    let result = client
-        .get("https://slack.com/api/conversations.history")
-        .query(&[
-            ("channel", channel),
-            ("latest", ts),
-            ("limit", count.to_string().as_ref()),
-            ("inclusive", "true"),
-        ])
-        .bearer_auth(token)
-        .send()
-        .await;
-
-    match result {
-        Ok(resp) => {
-            log::debug!("{:#?}", resp);
-            let data = resp
-                .json::<ConversationsHistoryResponse>()
-                .await
-                .map_err(|e| error!("{}", e))?;
-
-            Ok(data.messages)
-        }
-
-        Err(e) => {
-            error!("{}", e);
-            Err(())
-        }
-    }
+// https://api.slack.com/types/file
+// Files hidden by the plan's storage limit come with only a few fields.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SlackFile {
+    pub id: String,
+    pub name: String,
+    pub mimetype: String,
+    pub filetype: String,
+    pub url_private: String,
+    pub permalink: String,
+    /// Bytes
+    pub size: u64,
 }
 
-#[derive(Deserialize)]
-struct UserInfoResponse {
-    ok: bool,
-    user: SlackUser,
+// Raster formats only. SVG is an image too, but it can run scripts.
+const IMAGE_MIMETYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+impl SlackFile {
+    pub fn is_image(&self) -> bool {
+        IMAGE_MIMETYPES.contains(&self.mimetype.as_str())
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct SlackUser {
     pub id: String,
     pub name: String,
     pub team_id: String,
 }
 
-#[derive(Debug)]
-enum SlackClientError {
-    ApiError,
-    JsonError,
+/// Escapes text to be embedded in mrkdwn.
+/// https://api.slack.com/reference/surfaces/formatting#escaping
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The Slack Web API methods the app calls, authenticated with a workspace's bot `token`.
+/// `SlackWebApi` calls Slack, and tests point it at a local fake.
+#[async_trait]
+pub trait SlackApi: Send + Sync {
+    /// A user id as `channel` sends a direct message from the app.
+    async fn post_message(
+        &self,
+        token: &str,
+        channel: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError>;
+
+    async fn post_ephemeral(
+        &self,
+        token: &str,
+        channel: &str,
+        user: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError>;
+
+    async fn update_message(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError>;
+
+    /// Ephemeral messages can't be updated with chat.update, only through the response_url of
+    /// an interaction with them.
+    async fn replace_original(
+        &self,
+        response_url: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError>;
+
+    async fn open_view(
+        &self,
+        token: &str,
+        trigger_id: &str,
+        view: &serde_json::Value,
+    ) -> Result<(), SlackClientError>;
+
+    /// Reacting twice with the same emoji is not an error.
+    async fn add_reaction(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        name: &str,
+    ) -> Result<(), SlackClientError>;
+
+    async fn get_messages(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        count: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError>;
+
+    /// The first message is the thread parent, followed by replies in chronological order.
+    async fn get_replies(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        limit: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError>;
+
+    async fn get_file_info(&self, token: &str, file: &str) -> Result<SlackFile, SlackClientError>;
+
+    /// Downloads a private file. `url_private` needs the bot token to be fetched.
+    /// Files over `max_size` bytes aren't downloaded.
+    async fn download_file(
+        &self,
+        token: &str,
+        url_private: &str,
+        max_size: u64,
+    ) -> Result<actix_web::web::Bytes, SlackClientError>;
+
+    async fn get_permalink(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+    ) -> Result<String, SlackClientError>;
+
+    async fn get_user_info(&self, token: &str, user: &str) -> Result<SlackUser, SlackClientError>;
+
+    async fn get_channel_name(
+        &self,
+        token: &str,
+        channel: &str,
+    ) -> Result<String, SlackClientError>;
+
+    /// Returns the handles of the team's user groups by id.
+    async fn get_usergroup_handles(
+        &self,
+        token: &str,
+    ) -> Result<HashMap<String, String>, SlackClientError>;
+}
+
+/// Slack Web API client authenticated as a workspace's bot.
+#[derive(Clone)]
+pub struct SlackClient {
+    team_id: String,
+    token: String,
+    api: Arc<dyn SlackApi>,
 }
 
-impl std::fmt::Display for SlackClientError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            SlackClientError::ApiError => write!(f, "api error"),
-            SlackClientError::JsonError => write!(f, "json parse error"),
+impl SlackClient {
+    pub fn new(team_id: String, token: String, api: Arc<dyn SlackApi>) -> Self {
+        SlackClient {
+            team_id,
+            token,
+            api,
         }
     }
-}
 
-impl std::error::Error for SlackClientError {}
+    pub async fn post_message(
+        &self,
+        channel: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError> {
+        self.api
+            .post_message(&self.token, channel, text, blocks, thread_ts)
+            .await
+    }
+
+    pub async fn post_ephemeral(
+        &self,
+        channel: &str,
+        user: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError> {
+        self.api
+            .post_ephemeral(&self.token, channel, user, text, blocks, thread_ts)
+            .await
+    }
+
+    pub async fn update_message(
+        &self,
+        channel: &str,
+        ts: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError> {
+        self.api
+            .update_message(&self.token, channel, ts, text, blocks)
+            .await
+    }
+
+    pub async fn replace_original(
+        &self,
+        response_url: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError> {
+        self.api.replace_original(response_url, text, blocks).await
+    }
+
+    pub async fn open_view(
+        &self,
+        trigger_id: &str,
+        view: &serde_json::Value,
+    ) -> Result<(), SlackClientError> {
+        self.api.open_view(&self.token, trigger_id, view).await
+    }
+
+    /// Tells `user` about the message at `thread_ts` in `channel`, the way `mode` says.
+    pub async fn reply(
+        &self,
+        mode: ReplyMode,
+        channel: &str,
+        thread_ts: &str,
+        user: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError> {
+        match mode {
+            ReplyMode::Thread => {
+                self.post_message(channel, text, blocks, Some(thread_ts))
+                    .await
+            }
+            ReplyMode::Ephemeral => {
+                self.post_ephemeral(channel, user, text, blocks, Some(thread_ts))
+                    .await
+            }
+            ReplyMode::Dm => self.post_message(user, text, blocks, None).await,
+            ReplyMode::Silent => Ok(()),
+        }
+    }
+
+    pub async fn add_reaction(
+        &self,
+        channel: &str,
+        ts: &str,
+        name: &str,
+    ) -> Result<(), SlackClientError> {
+        self.api.add_reaction(&self.token, channel, ts, name).await
+    }
+
+    pub async fn get_messages(
+        &self,
+        channel: &str,
+        ts: &str,
+        count: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError> {
+        self.api.get_messages(&self.token, channel, ts, count).await
+    }
+
+    pub async fn get_replies(
+        &self,
+        channel: &str,
+        ts: &str,
+        limit: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError> {
+        self.api.get_replies(&self.token, channel, ts, limit).await
+    }
 
-pub async fn get_user_info(user: &str) -> Result<SlackUser, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let token = env::var("SLACK_TOKEN").unwrap_or_default();
-    let result = client
-        .get("https://slack.com/api/users.info")
-        .query(&[("user", user)])
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|_e| SlackClientError::ApiError)?
-        .json::<UserInfoResponse>()
-        .await
-        .map_err(|_e| SlackClientError::JsonError)?;
+    pub async fn get_file_info(&self, file: &str) -> Result<SlackFile, SlackClientError> {
+        self.api.get_file_info(&self.token, file).await
+    }
 
-    Ok(result.user)
+    pub async fn download_file(
+        &self,
+        url_private: &str,
+        max_size: u64,
+    ) -> Result<actix_web::web::Bytes, SlackClientError> {
+        self.api
+            .download_file(&self.token, url_private, max_size)
+            .await
+    }
+
+    pub async fn get_permalink(&self, channel: &str, ts: &str) -> Result<String, SlackClientError> {
+        self.api.get_permalink(&self.token, channel, ts).await
+    }
 }
 
-#[derive(Deserialize)]
-struct GetPermalinkResponse {
-    permalink: String,
-}
-
-pub async fn get_permalink(channel: &str, ts: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-    let token = env::var("SLACK_TOKEN").unwrap_or_default();
-    let data = client
-        .get("https://slack.com/api/chat.getPermalink")
-        .query(&[("channel", channel), ("message_ts", ts)])
-        .bearer_auth(token)
-        .send()
-        .await
-        .map_err(|_e| SlackClientError::ApiError)?
-        .json::<GetPermalinkResponse>()
-        .await
-        .map_err(|_e| SlackClientError::JsonError)?;
-
-    Ok(data.permalink)
+#[cfg(test)]
+mod tests {
+    use super::{ReplyMode, SlackFile};
+
+    #[test]
+    fn test_is_image() {
+        let file = |mimetype: &str| SlackFile {
+            mimetype: mimetype.to_string(),
+            ..Default::default()
+        };
+        assert!(file("image/png").is_image());
+        assert!(file("image/webp").is_image());
+        assert!(!file("image/svg+xml").is_image());
+        assert!(!file("text/html").is_image());
+        assert!(!file("").is_image());
+    }
+
+    #[test]
+    fn test_reply_mode() {
+        for mode in [
+            ReplyMode::Thread,
+            ReplyMode::Ephemeral,
+            ReplyMode::Dm,
+            ReplyMode::Silent,
+        ] {
+            assert_eq!(mode.as_str().parse::<ReplyMode>(), Ok(mode));
+            assert_eq!(
+                serde_json::to_value(mode).unwrap(),
+                serde_json::json!(mode.as_str())
+            );
+        }
+        assert!("channel".parse::<ReplyMode>().is_err());
+    }
 }
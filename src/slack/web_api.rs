@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+use crate::http_client::{HttpClient, RequestBuilder};
+
+use super::{error, SlackApi, SlackClientError, SlackFile, SlackMessage, SlackUser};
+
+pub const SLACK_API_URL: &str = "https://slack.com/api";
+
+/// `SlackApi` over HTTP. `base_url` is where methods are called, e.g. `https://slack.com/api`.
+pub struct SlackWebApi {
+    client: HttpClient,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct ConversationsHistoryResponse {
+    messages: Vec<SlackMessage>,
+}
+
+#[derive(Deserialize)]
+struct FileInfoResponse {
+    file: SlackFile,
+}
+
+#[derive(Deserialize)]
+struct GetPermalinkResponse {
+    permalink: String,
+}
+
+#[derive(Deserialize)]
+struct UserInfoResponse {
+    user: SlackUser,
+}
+
+#[derive(Deserialize)]
+struct ConversationsInfoResponse {
+    channel: Channel,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct UsergroupsListResponse {
+    usergroups: Vec<Usergroup>,
+}
+
+#[derive(Deserialize)]
+struct Usergroup {
+    id: String,
+    handle: String,
+}
+
+// `text` is shown in notifications, and in place of `blocks` where they can't be rendered.
+fn message_data(text: &str, blocks: &[serde_json::Value]) -> serde_json::Value {
+    let mut data = json!({ "text": text });
+    if !blocks.is_empty() {
+        data["blocks"] = json!(blocks);
+    }
+    data
+}
+
+impl SlackWebApi {
+    pub fn new(client: HttpClient, base_url: &str) -> Self {
+        SlackWebApi {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn url(&self, method: &str) -> String {
+        format!("{}/{}", self.base_url, method)
+    }
+
+    async fn post_json(
+        &self,
+        token: &str,
+        method: &str,
+        data: &serde_json::Value,
+    ) -> Result<(), SlackClientError> {
+        self.call::<serde_json::Value>(
+            token,
+            self.client
+                .post(self.url(method))
+                .header("Content-Type", "application/json")
+                .json(data),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sends a Web API request as the bot, and parses the response or the error it reports.
+    async fn call<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        request: RequestBuilder,
+    ) -> Result<T, SlackClientError> {
+        let resp = request.bearer_auth(token).send().await?;
+        log::debug!("{:#?}", resp);
+        let status = resp.status();
+        let body = resp.text().await?;
+
+        error::parse_response(status, body)
+    }
+}
+
+#[async_trait]
+impl SlackApi for SlackWebApi {
+    // https://api.slack.com/methods/chat.postMessage
+    async fn post_message(
+        &self,
+        token: &str,
+        channel: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError> {
+        let mut data = message_data(text, blocks);
+        data["channel"] = json!(channel);
+        if let Some(thread_ts) = thread_ts {
+            data["thread_ts"] = json!(thread_ts);
+        }
+
+        self.post_json(token, "chat.postMessage", &data).await
+    }
+
+    // https://api.slack.com/methods/chat.postEphemeral
+    async fn post_ephemeral(
+        &self,
+        token: &str,
+        channel: &str,
+        user: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+        thread_ts: Option<&str>,
+    ) -> Result<(), SlackClientError> {
+        let mut data = message_data(text, blocks);
+        data["channel"] = json!(channel);
+        data["user"] = json!(user);
+        if let Some(thread_ts) = thread_ts {
+            data["thread_ts"] = json!(thread_ts);
+        }
+
+        self.post_json(token, "chat.postEphemeral", &data).await
+    }
+
+    // https://api.slack.com/methods/chat.update
+    async fn update_message(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError> {
+        let mut data = message_data(text, blocks);
+        data["channel"] = json!(channel);
+        data["ts"] = json!(ts);
+
+        self.post_json(token, "chat.update", &data).await
+    }
+
+    // https://api.slack.com/interactivity/handling#message_responses
+    async fn replace_original(
+        &self,
+        response_url: &str,
+        text: &str,
+        blocks: &[serde_json::Value],
+    ) -> Result<(), SlackClientError> {
+        let mut data = message_data(text, blocks);
+        data["replace_original"] = json!(true);
+
+        // The url authorizes the request by itself, and answers with a plain `ok`
+        let resp = self.client.post(response_url).json(&data).send().await?;
+        log::debug!("{:#?}", resp);
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(SlackClientError::Api {
+                status: status.as_u16(),
+                code: format!("http_{}", status.as_u16()),
+                body: resp.text().await.unwrap_or_default(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // https://api.slack.com/methods/views.open
+    async fn open_view(
+        &self,
+        token: &str,
+        trigger_id: &str,
+        view: &serde_json::Value,
+    ) -> Result<(), SlackClientError> {
+        let data = json!({ "trigger_id": trigger_id, "view": view });
+
+        self.post_json(token, "views.open", &data).await
+    }
+
+    // https://api.slack.com/methods/reactions.add
+    async fn add_reaction(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        name: &str,
+    ) -> Result<(), SlackClientError> {
+        let mut data = HashMap::new();
+        data.insert("channel", channel);
+        data.insert("timestamp", ts);
+        data.insert("name", name);
+
+        let result = self
+            .call::<serde_json::Value>(
+                token,
+                self.client
+                    .post(self.url("reactions.add"))
+                    .header("Content-Type", "application/json")
+                    .json(&data),
+            )
+            .await;
+
+        match result {
+            Err(e) if e.code() == Some("already_reacted") => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    // https://api.slack.com/methods/conversations.history
+    async fn get_messages(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        count: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError> {
+        let data: ConversationsHistoryResponse = self
+            .call(
+                token,
+                self.client.get(self.url("conversations.history")).query(&[
+                    ("channel", channel),
+                    ("latest", ts),
+                    ("limit", count.to_string().as_ref()),
+                    ("inclusive", "true"),
+                ]),
+            )
+            .await?;
+
+        Ok(data.messages)
+    }
+
+    // https://api.slack.com/methods/conversations.replies
+    async fn get_replies(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+        limit: u32,
+    ) -> Result<Vec<SlackMessage>, SlackClientError> {
+        let data: ConversationsHistoryResponse = self
+            .call(
+                token,
+                self.client.get(self.url("conversations.replies")).query(&[
+                    ("channel", channel),
+                    ("ts", ts),
+                    ("limit", limit.to_string().as_ref()),
+                ]),
+            )
+            .await?;
+
+        Ok(data.messages)
+    }
+
+    // https://api.slack.com/methods/files.info
+    async fn get_file_info(&self, token: &str, file: &str) -> Result<SlackFile, SlackClientError> {
+        let data: FileInfoResponse = self
+            .call(
+                token,
+                self.client
+                    .get(self.url("files.info"))
+                    .query(&[("file", file)]),
+            )
+            .await?;
+
+        Ok(data.file)
+    }
+
+    // https://api.slack.com/types/file#authentication
+    async fn download_file(
+        &self,
+        token: &str,
+        url_private: &str,
+        max_size: u64,
+    ) -> Result<actix_web::web::Bytes, SlackClientError> {
+        let mut resp = self
+            .client
+            .get(url_private)
+            .bearer_auth(token)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(SlackClientError::Api {
+                status: status.as_u16(),
+                code: format!("http_{}", status.as_u16()),
+                body: resp.text().await.unwrap_or_default(),
+            });
+        }
+
+        let too_large = || SlackClientError::Api {
+            status: status.as_u16(),
+            code: "file_too_large".to_string(),
+            body: String::new(),
+        };
+        if resp
+            .content_length()
+            .is_some_and(|length| length > max_size)
+        {
+            return Err(too_large());
+        }
+        // Content-Length may be missing, so the body is counted as it comes
+        let mut content = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            content.extend_from_slice(&chunk);
+            if content.len() as u64 > max_size {
+                return Err(too_large());
+            }
+        }
+
+        Ok(content.into())
+    }
+
+    // https://api.slack.com/methods/chat.getPermalink
+    async fn get_permalink(
+        &self,
+        token: &str,
+        channel: &str,
+        ts: &str,
+    ) -> Result<String, SlackClientError> {
+        let data: GetPermalinkResponse = self
+            .call(
+                token,
+                self.client
+                    .get(self.url("chat.getPermalink"))
+                    .query(&[("channel", channel), ("message_ts", ts)]),
+            )
+            .await?;
+
+        Ok(data.permalink)
+    }
+
+    // https://api.slack.com/methods/users.info
+    async fn get_user_info(&self, token: &str, user: &str) -> Result<SlackUser, SlackClientError> {
+        let data: UserInfoResponse = self
+            .call(
+                token,
+                self.client
+                    .get(self.url("users.info"))
+                    .query(&[("user", user)]),
+            )
+            .await?;
+
+        Ok(data.user)
+    }
+
+    // https://api.slack.com/methods/conversations.info
+    async fn get_channel_name(
+        &self,
+        token: &str,
+        channel: &str,
+    ) -> Result<String, SlackClientError> {
+        let data: ConversationsInfoResponse = self
+            .call(
+                token,
+                self.client
+                    .get(self.url("conversations.info"))
+                    .query(&[("channel", channel)]),
+            )
+            .await?;
+
+        Ok(data.channel.name)
+    }
+
+    // https://api.slack.com/methods/usergroups.list
+    async fn get_usergroup_handles(
+        &self,
+        token: &str,
+    ) -> Result<HashMap<String, String>, SlackClientError> {
+        let data: UsergroupsListResponse = self
+            .call(token, self.client.get(self.url("usergroups.list")))
+            .await?;
+
+        Ok(data
+            .usergroups
+            .into_iter()
+            .map(|group| (group.id, group.handle))
+            .collect())
+    }
+}
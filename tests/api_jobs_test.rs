@@ -0,0 +1,109 @@
+use emoji_to_do::entities;
+
+use sea_orm::{EntityTrait, Set};
+use serde::Deserialize;
+
+use test::{create_api_client, create_user};
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[derive(Deserialize)]
+struct JobResponse {
+    id: i32,
+    status: String,
+    attempts: i32,
+}
+
+async fn create_job(
+    connection: &sea_orm::DatabaseConnection,
+    slack_team_id: &str,
+    status: &str,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let job_id = entities::job::Entity::insert(entities::job::ActiveModel {
+        slack_team_id: Set(slack_team_id.to_owned()),
+        payload: Set(
+            r#"{"type":"app_mention","user":"U1234","channel":"C1234","text":"ping"}"#.to_owned(),
+        ),
+        status: Set(status.to_owned()),
+        attempts: Set(5),
+        run_at: Set(0),
+        last_error: Set(Some("api error".to_owned())),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    Ok(job_id)
+}
+
+#[actix_rt::test]
+async fn test_api_jobs() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let dead_job_id = create_job(&connection, &user.slack_team_id, "dead").await?;
+    let _pending_job_id = create_job(&connection, &user.slack_team_id, "pending").await?;
+    let _other_team_job_id = create_job(&connection, "OTHER", "dead").await?;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .get(format!("{}/api/jobs", host))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+    let jobs: Vec<JobResponse> = response.json().await?;
+    assert_eq!(jobs.len(), 2);
+
+    let response = client
+        .get(format!("{}/api/jobs?status=dead", host))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+    let jobs: Vec<JobResponse> = response.json().await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].id, dead_job_id);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_retry_job() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let dead_job_id = create_job(&connection, &user.slack_team_id, "dead").await?;
+    let pending_job_id = create_job(&connection, &user.slack_team_id, "pending").await?;
+    let other_team_job_id = create_job(&connection, "OTHER", "dead").await?;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .post(format!("{}/api/jobs/{}/retry", host, dead_job_id))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+    let job: JobResponse = response.json().await?;
+    assert_eq!(job.status, "pending");
+    assert_eq!(job.attempts, 0);
+
+    let response = client
+        .post(format!("{}/api/jobs/{}/retry", host, pending_job_id))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .post(format!("{}/api/jobs/{}/retry", host, other_team_job_id))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 404);
+
+    Ok(())
+}
@@ -240,3 +240,217 @@ async fn test_api_destroy_reaction() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[actix_rt::test]
+async fn test_api_create_reaction_include_thread() -> Result<(), Box<dyn std::error::Error>> {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(user.slack_team_id),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?
+    .last_insert_id;
+
+    let client = create_api_client(user.id)?;
+    for (name, include_thread, expected) in [
+        ("eyes", None, true),
+        ("bug", Some(false), false),
+        ("memo", Some(true), true),
+    ] {
+        let mut body = json!({
+            "name": name,
+            "repo": "uiur/sandbox",
+            "reaction_assignees": []
+        });
+        if let Some(include_thread) = include_thread {
+            body["include_thread"] = json!(include_thread);
+        }
+
+        let response = client
+            .post(format!("{}/api/teams/{}/reactions", host, team_id))
+            .json(&body)
+            .send()
+            .await
+            .expect("failed to fetch api");
+        assert_eq!(response.status().as_u16(), 201);
+
+        let json: CreateReactionResponse = response.json().await?;
+        let reaction = entities::prelude::Reaction::find_by_id(json.id)
+            .one(&connection)
+            .await?
+            .unwrap();
+        assert_eq!(reaction.include_thread, expected);
+    }
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_create_reaction_with_issue_options() -> Result<(), Box<dyn std::error::Error>> {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(user.slack_team_id),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?
+    .last_insert_id;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .post(format!("{}/api/teams/{}/reactions", host, team_id))
+        .json(&json!({
+            "name": "bug",
+            "repo": "uiur/sandbox",
+            "milestone": 3,
+            "issue_type": "Bug",
+            "reply_mode": "dm",
+            "confirm": true,
+            "reaction_assignees": []
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let json: CreateReactionResponse = response.json().await?;
+    let reaction = entities::prelude::Reaction::find_by_id(json.id)
+        .one(&connection)
+        .await?
+        .unwrap();
+    assert_eq!(reaction.milestone, Some(3));
+    assert_eq!(reaction.issue_type.as_deref(), Some("Bug"));
+    assert_eq!(reaction.reply_mode.as_deref(), Some("dm"));
+    assert!(reaction.confirm);
+
+    let response = client
+        .get(format!("{}/api/reactions/{}", host, reaction.id))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let json: serde_json::Value = response.json().await?;
+    assert_eq!(json["milestone"], 3);
+    assert_eq!(json["issue_type"], "Bug");
+    assert_eq!(json["reply_mode"], "dm");
+    assert_eq!(json["confirm"], true);
+    assert_eq!(json["reaction_labels"], json!([]));
+
+    // Labels can't be checked against the repository without the GitHub App
+    let response = client
+        .post(format!("{}/api/teams/{}/reactions", host, team_id))
+        .json(&json!({
+            "name": "memo",
+            "repo": "uiur/sandbox",
+            "reaction_assignees": [],
+            "reaction_labels": [{ "name": "bug" }]
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 400);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_create_reaction_with_invalid_template() -> Result<(), Box<dyn std::error::Error>>
+{
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(user.slack_team_id),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?
+    .last_insert_id;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .post(format!("{}/api/teams/{}/reactions", host, team_id))
+        .json(&json!({
+            "name": "eyes",
+            "repo": "uiur/sandbox",
+            "title_template": "{{#each messages}}",
+            "reaction_assignees": []
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .post(format!("{}/api/teams/{}/reactions", host, team_id))
+        .json(&json!({
+            "name": "eyes",
+            "repo": "uiur/sandbox",
+            "title_template": "[{{reactioner}}] {{message.text}}",
+            "body_template": "{{#each messages}}> {{text}}\n{{/each}}",
+            "reaction_assignees": []
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let json: CreateReactionResponse = response.json().await?;
+    let reaction = entities::prelude::Reaction::find_by_id(json.id)
+        .one(&connection)
+        .await?
+        .unwrap();
+    assert_eq!(
+        reaction.title_template.as_deref(),
+        Some("[{{reactioner}}] {{message.text}}")
+    );
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct PreviewReactionResponse {
+    title: String,
+    body: String,
+}
+
+#[actix_rt::test]
+async fn test_api_preview_reaction() -> Result<(), Box<dyn std::error::Error>> {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let client = create_api_client(user.id)?;
+    let response = client
+        .post(format!("{}/api/reactions/preview", host))
+        .json(&json!({
+            "title_template": "[{{reactioner}}] {{message.text}}",
+            "body_template": "{{#each authors}}@{{this}} {{/each}}"
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let json: PreviewReactionResponse = response.json().await?;
+    assert_eq!(json.title, "[carol] the build on main is failing");
+    assert_eq!(json.body, "@alice @bob ");
+
+    let response = client
+        .post(format!("{}/api/reactions/preview", host))
+        .json(&json!({ "title_template": "{{/if}}" }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 400);
+
+    Ok(())
+}
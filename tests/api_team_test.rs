@@ -21,7 +21,7 @@ async fn test_api_team_when_authenticated() -> Result<(), Box<dyn std::error::Er
     let user_id = entities::user::Entity::insert(entities::user::ActiveModel {
         slack_team_id: Set("TEAM".to_owned()),
         slack_user_id: Set("USER".to_owned()),
-        slack_token: Set("TOKEN".to_owned()),
+        slack_token: Set(Some("TOKEN".to_owned())),
         ..Default::default()
     })
     .exec(&connection)
@@ -61,3 +61,47 @@ async fn test_api_team_when_authenticated() -> Result<(), Box<dyn std::error::Er
 
     Ok(())
 }
+
+#[actix_rt::test]
+async fn test_api_update_team() -> Result<(), Box<dyn std::error::Error>> {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = test::create_user(&connection).await?;
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(user.slack_team_id),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?
+    .last_insert_id;
+
+    let client = test::create_api_client(user.id)?;
+    let response = client
+        .put(format!("{}/api/team", host))
+        .json(&serde_json::json!({
+            "closed_issue_reaction": ":tada:",
+            "reply_mode": "ephemeral"
+        }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let team = entities::prelude::Team::find_by_id(team_id)
+        .one(&connection)
+        .await?
+        .expect("team is not found");
+    assert_eq!(team.closed_issue_reaction.as_deref(), Some("tada"));
+    assert_eq!(team.reply_mode, "ephemeral");
+
+    let response = client
+        .put(format!("{}/api/team", host))
+        .json(&serde_json::json!({ "reply_mode": "loud" }))
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 400);
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use emoji_to_do::entities;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+
+use test::{create_api_client, create_user};
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+async fn create_team(
+    connection: &sea_orm::DatabaseConnection,
+    slack_team_id: &str,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(slack_team_id.to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+    Ok(team_id)
+}
+
+#[actix_rt::test]
+async fn test_api_put_user_link() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = create_team(&connection, &user.slack_team_id).await?;
+
+    let client = create_api_client(user.id)?;
+    for github_login in ["@uiur", "uiur-dev"] {
+        let response = client
+            .put(format!("{}/api/teams/{}/user_links/U1234", host, team_id))
+            .json(&json!({ "github_login": github_login }))
+            .send()
+            .await?;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let user_links = entities::prelude::UserLink::find()
+        .filter(entities::user_link::Column::TeamId.eq(team_id))
+        .all(&connection)
+        .await?;
+    assert_eq!(user_links.len(), 1);
+    assert_eq!(user_links[0].slack_user_id, "U1234");
+    assert_eq!(user_links[0].github_login, "uiur-dev");
+
+    let response = client
+        .get(format!("{}/api/teams/{}/user_links", host, team_id))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await?;
+    assert_eq!(body[0]["github_login"], "uiur-dev");
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_put_user_link_with_invalid_login() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = create_team(&connection, &user.slack_team_id).await?;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .put(format!("{}/api/teams/{}/user_links/U1234", host, team_id))
+        .json(&json!({ "github_login": "not a login" }))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 400);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_user_links_of_other_team() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = create_team(&connection, "OTHER TEAM").await?;
+
+    let client = create_api_client(user.id)?;
+    let response = client
+        .get(format!("{}/api/teams/{}/user_links", host, team_id))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = client
+        .put(format!("{}/api/teams/{}/user_links/U1234", host, team_id))
+        .json(&json!({ "github_login": "uiur" }))
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 404);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_api_destroy_user_link() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let user = create_user(&connection).await?;
+    let team_id = create_team(&connection, &user.slack_team_id).await?;
+    entities::user_link::Entity::insert(entities::user_link::ActiveModel {
+        team_id: Set(team_id),
+        slack_user_id: Set("U1234".to_owned()),
+        github_login: Set("uiur".to_owned()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+
+    let client = create_api_client(user.id)?;
+    let url = format!("{}/api/teams/{}/user_links/U1234", host, team_id);
+    let response = client.delete(&url).send().await?;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let user_links = entities::prelude::UserLink::find().all(&connection).await?;
+    assert!(user_links.is_empty());
+
+    let response = client.delete(&url).send().await?;
+    assert_eq!(response.status().as_u16(), 404);
+
+    Ok(())
+}
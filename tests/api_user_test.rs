@@ -26,7 +26,7 @@ async fn test_api_user_when_authenticated() -> Result<(), Box<dyn std::error::Er
     let user_id = entities::user::Entity::insert(entities::user::ActiveModel {
         slack_team_id: Set("TEAM".to_owned()),
         slack_user_id: Set("USER".to_owned()),
-        slack_token: Set("TOKEN".to_owned()),
+        slack_token: Set(Some("TOKEN".to_owned())),
         ..Default::default()
     })
     .exec(&connection)
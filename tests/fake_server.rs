@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use emoji_to_do::Services;
+use serde_json::json;
+
+/// A request the app made to the fake server.
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub query: HashMap<String, String>,
+    /// `null` unless the request was JSON
+    pub body: serde_json::Value,
+    pub authorization: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    responses: HashMap<(String, String), (u16, serde_json::Value)>,
+    calls: Vec<(String, String, Call)>,
+}
+
+/// Stands in for the Slack Web API under `/slack` and the GitHub REST API under `/github`,
+/// answering with stubbed responses and recording every request.
+/// Slack methods that aren't stubbed answer `{"ok": true}`, and GitHub paths 404.
+pub struct FakeServer {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let data = web::Data::from(state.clone());
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .default_service(web::to(respond))
+        })
+        .workers(1)
+        .listen(listener)
+        .expect("failed to listen")
+        .run();
+        actix_rt::spawn(server);
+
+        FakeServer {
+            url: format!("http://127.0.0.1:{}", port),
+            state,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Clients of the app calling this server instead of Slack and GitHub.
+    pub fn services(&self) -> Services {
+        Services::with_base_urls(
+            &format!("{}/slack", self.url),
+            &format!("{}/github", self.url),
+        )
+    }
+
+    /// Answers `method` requests to `path`, e.g. `/slack/chat.postMessage`, with `body`.
+    pub fn stub(&self, method: &str, path: &str, status: u16, body: serde_json::Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .insert((method.to_owned(), path.to_owned()), (status, body));
+    }
+
+    /// The `method` requests made to `path` so far, oldest first.
+    pub fn calls(&self, method: &str, path: &str) -> Vec<Call> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|(m, p, _)| m == method && p == path)
+            .map(|(_, _, call)| call.clone())
+            .collect()
+    }
+}
+
+async fn respond(
+    req: HttpRequest,
+    body: web::Bytes,
+    state: web::Data<Mutex<State>>,
+) -> HttpResponse {
+    let method = req.method().to_string();
+    let path = req.path().to_owned();
+    let call = Call {
+        query: serde_urlencoded::from_str(req.query_string()).unwrap_or_default(),
+        body: serde_json::from_slice(&body).unwrap_or_default(),
+        authorization: req
+            .headers()
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned),
+    };
+
+    let mut state = state.lock().unwrap();
+    state.calls.push((method.clone(), path.clone(), call));
+
+    let (status, body) = match state.responses.get(&(method, path.clone())) {
+        Some(response) => response.clone(),
+        None if path.starts_with("/slack/") => (200, json!({ "ok": true })),
+        None => (404, json!({ "message": "Not Found" })),
+    };
+    HttpResponse::build(actix_web::http::StatusCode::from_u16(status).unwrap()).json(body)
+}
@@ -0,0 +1,136 @@
+use emoji_to_do::entities;
+
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+async fn create_message_issue(
+    connection: &DatabaseConnection,
+    slack_team_id: &str,
+    repo: &str,
+    issue_number: i32,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(slack_team_id.to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    let message_issue_id =
+        entities::message_issue::Entity::insert(entities::message_issue::ActiveModel {
+            team_id: Set(team_id),
+            channel: Set("C1234".to_owned()),
+            ts: Set("1660000000.000100".to_owned()),
+            repo: Set(repo.to_owned()),
+            issue_number: Set(issue_number),
+            html_url: Set(format!(
+                "https://github.com/{}/issues/{}",
+                repo, issue_number
+            )),
+            ..Default::default()
+        })
+        .exec(connection)
+        .await?
+        .last_insert_id;
+
+    Ok(message_issue_id)
+}
+
+fn issues_event(action: &str, repo: &str, issue_number: i32) -> serde_json::Value {
+    json!({
+        "action": action,
+        "issue": {
+            "number": issue_number,
+            "title": "build fails",
+            "html_url": format!("https://github.com/{}/issues/{}", repo, issue_number)
+        },
+        "repository": { "full_name": repo },
+        "sender": { "login": "uiur", "type": "User" }
+    })
+}
+
+#[actix_rt::test]
+async fn test_github_events_issue_closed_enqueues_job() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let message_issue_id = create_message_issue(&connection, "TEAM", "uiur/sandbox", 12).await?;
+    create_message_issue(&connection, "OTHER", "uiur/other", 12).await?;
+
+    let response =
+        test::post_github_event(&host, "issues", &issues_event("closed", "uiur/sandbox", 12))
+            .await
+            .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].slack_team_id, "TEAM");
+
+    let payload: serde_json::Value = serde_json::from_str(&jobs[0].payload)?;
+    assert_eq!(payload["type"], "issue_updated");
+    assert_eq!(payload["message_issue_id"], message_issue_id);
+    assert_eq!(payload["closed"], true);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_github_events_ignored() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    create_message_issue(&connection, "TEAM", "uiur/sandbox", 12).await?;
+
+    let cases = [
+        // not linked to a slack message
+        ("issues", issues_event("closed", "uiur/sandbox", 13)),
+        // not interesting
+        ("issues", issues_event("labeled", "uiur/sandbox", 12)),
+        ("ping", json!({ "zen": "Keep it logically awesome." })),
+    ];
+    for (event, payload) in cases {
+        let response = test::post_github_event(&host, event, &payload)
+            .await
+            .expect("failed to fetch api");
+        assert_eq!(response.status().as_u16(), 200, "{}", event);
+    }
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert!(jobs.is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_github_events_with_invalid_signature() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/webhook/github", host))
+        .header("Content-Type", "application/json")
+        .header("X-GitHub-Event", "issues")
+        .header("X-Hub-Signature-256", "sha256=deadbeef")
+        .body(issues_event("closed", "uiur/sandbox", 12).to_string())
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 401);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_github_event_with_too_large_body() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+
+    let event = json!({ "padding": "a".repeat(2 * 1024 * 1024) });
+    let response = test::post_github_event(&host, "issues", &event).await?;
+    assert_eq!(response.status().as_u16(), 413);
+
+    Ok(())
+}
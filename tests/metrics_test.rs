@@ -0,0 +1,38 @@
+use test::spawn_app;
+mod test;
+
+#[actix_rt::test]
+async fn test_metrics_returns_prometheus_text() {
+    let (host, _) = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/metrics", host))
+        .bearer_auth("metrics token for test")
+        .send()
+        .await
+        .expect("failed to execute request");
+
+    assert!(response.status().is_success());
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let text = response.text().await.unwrap();
+    assert!(text.contains("# TYPE http_client_requests_total counter\n"));
+}
+
+#[actix_rt::test]
+async fn test_metrics_without_token() {
+    let (host, _) = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    for token in [None, Some("wrong token")] {
+        let mut request = client.get(format!("{}/metrics", host));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.expect("failed to execute request");
+        assert_eq!(response.status().as_u16(), 401, "{:?}", token);
+    }
+}
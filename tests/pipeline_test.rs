@@ -0,0 +1,817 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use emoji_to_do::{entities, secret};
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use serde_json::json;
+
+mod fake_server;
+mod test;
+
+use fake_server::FakeServer;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const MESSAGE_TS: &str = "1660000000.000100";
+
+// Teams and installations are distinct per test, since Slack lookups and installation tokens
+// are cached across apps.
+async fn create_team(
+    connection: &DatabaseConnection,
+    slack_team_id: &str,
+    github_installation_id: i32,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(slack_team_id.to_owned()),
+        slack_bot_token: Set(Some(secret::encrypt("xoxb-test")?)),
+        github_installation_id: Set(Some(github_installation_id)),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    entities::reaction::Entity::insert(entities::reaction::ActiveModel {
+        team_id: Set(team_id),
+        name: Set("bug".to_owned()),
+        repo: Set("uiur/bugs".to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?;
+
+    Ok(team_id)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn reaction_added_event(slack_team_id: &str, event_id: &str) -> serde_json::Value {
+    json!({
+        "type": "event_callback",
+        "event_id": event_id,
+        "team_id": slack_team_id,
+        "event_time": unix_now(),
+        "event": {
+            "type": "reaction_added",
+            "user": "U1234",
+            "reaction": "bug",
+            "item": {
+                "type": "message",
+                "channel": "C1234",
+                "ts": MESSAGE_TS
+            }
+        }
+    })
+}
+
+fn stub_slack_message(fake: &FakeServer, slack_team_id: &str) {
+    let messages = json!({
+        "ok": true,
+        "messages": [{ "user": "U1234", "text": "the build is broken", "ts": MESSAGE_TS }]
+    });
+    fake.stub("GET", "/slack/conversations.replies", 200, messages.clone());
+    fake.stub("GET", "/slack/conversations.history", 200, messages);
+    fake.stub(
+        "GET",
+        "/slack/users.info",
+        200,
+        json!({
+            "ok": true,
+            "user": { "id": "U1234", "name": "alice", "team_id": slack_team_id }
+        }),
+    );
+    fake.stub(
+        "GET",
+        "/slack/chat.getPermalink",
+        200,
+        json!({ "ok": true, "permalink": "https://example.slack.com/archives/C1234/p1660000000000100" }),
+    );
+}
+
+#[actix_rt::test]
+async fn test_reaction_added_files_issue() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE1", 101).await?;
+
+    stub_slack_message(&fake, "TPIPELINE1");
+    fake.stub(
+        "POST",
+        "/github/app/installations/101/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues",
+        201,
+        json!({ "number": 1, "html_url": "https://github.com/uiur/bugs/issues/1" }),
+    );
+
+    let response =
+        test::post_slack_event(&host, &reaction_added_event("TPIPELINE1", "EvPipeline1")).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    let lookups = fake.calls("GET", "/slack/conversations.replies");
+    assert_eq!(lookups[0].query["channel"], "C1234");
+    assert_eq!(lookups[0].query["ts"], MESSAGE_TS);
+
+    let issues = fake.calls("POST", "/github/repos/uiur/bugs/issues");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].authorization.as_deref(), Some("Bearer ghs_test"));
+    assert!(issues[0].body["title"]
+        .as_str()
+        .unwrap()
+        .contains("the build is broken"));
+
+    let replies = fake.calls("POST", "/slack/chat.postMessage");
+    assert_eq!(replies.len(), 1);
+    assert_eq!(
+        replies[0].authorization.as_deref(),
+        Some("Bearer xoxb-test")
+    );
+    assert_eq!(replies[0].body["channel"], "C1234");
+    assert_eq!(replies[0].body["thread_ts"], MESSAGE_TS);
+    assert!(replies[0].body["text"]
+        .as_str()
+        .unwrap()
+        .contains("https://github.com/uiur/bugs/issues/1"));
+
+    let message_issue = entities::prelude::MessageIssue::find()
+        .filter(entities::message_issue::Column::TeamId.eq(team_id))
+        .one(&connection)
+        .await?
+        .expect("message is not linked");
+    assert_eq!(message_issue.ts, MESSAGE_TS);
+    assert_eq!(message_issue.repo, "uiur/bugs");
+    assert_eq!(message_issue.issue_number, 1);
+
+    assert!(entities::prelude::Job::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_reaction_added_not_in_channel() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    create_team(&connection, "TPIPELINE2", 102).await?;
+
+    stub_slack_message(&fake, "TPIPELINE2");
+    fake.stub(
+        "GET",
+        "/slack/conversations.replies",
+        200,
+        json!({ "ok": false, "error": "not_in_channel" }),
+    );
+
+    let response =
+        test::post_slack_event(&host, &reaction_added_event("TPIPELINE2", "EvPipeline2")).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    // Retrying can't fix it, so the job is given up at once and the user is told what to do
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].status, "dead");
+
+    let messages = fake.calls("POST", "/slack/chat.postMessage");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].body["channel"], "U1234");
+    assert!(messages[0].body["text"]
+        .as_str()
+        .unwrap()
+        .contains("/invite @emoji-to-do"));
+
+    assert!(fake
+        .calls("POST", "/github/repos/uiur/bugs/issues")
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_reaction_added_reply_fails() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    create_team(&connection, "TPIPELINE3", 103).await?;
+
+    stub_slack_message(&fake, "TPIPELINE3");
+    fake.stub(
+        "POST",
+        "/github/app/installations/103/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues",
+        201,
+        json!({ "number": 3, "html_url": "https://github.com/uiur/bugs/issues/3" }),
+    );
+    fake.stub(
+        "POST",
+        "/slack/chat.postMessage",
+        200,
+        json!({ "ok": false, "error": "is_archived" }),
+    );
+
+    let response =
+        test::post_slack_event(&host, &reaction_added_event("TPIPELINE3", "EvPipeline3")).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    // The issue is filed, so the job isn't retried and nobody is told it failed
+    assert_eq!(
+        fake.calls("POST", "/github/repos/uiur/bugs/issues").len(),
+        1
+    );
+    assert_eq!(fake.calls("POST", "/slack/chat.postMessage").len(), 1);
+    assert!(entities::prelude::Job::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_invalid_job_payload() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (_host, connection) = test::spawn_app_with(services.clone()).await;
+
+    entities::job::Entity::insert(entities::job::ActiveModel {
+        slack_team_id: Set("TPIPELINE4".to_owned()),
+        payload: Set(r#"{"type":"unknown"}"#.to_owned()),
+        status: Set("pending".to_owned()),
+        attempts: Set(0),
+        run_at: Set(unix_now()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs[0].status, "dead");
+    assert_eq!(jobs[0].attempts, 1);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_reaction_added_while_message_is_being_filed() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE5", 105).await?;
+
+    stub_slack_message(&fake, "TPIPELINE5");
+    fake.stub(
+        "POST",
+        "/github/app/installations/105/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues/5/comments",
+        201,
+        json!({ "html_url": "https://github.com/uiur/bugs/issues/5#issuecomment-1" }),
+    );
+
+    // Another reaction's job is filing the message
+    entities::message_filing::Entity::insert(entities::message_filing::ActiveModel {
+        team_id: Set(team_id),
+        channel: Set("C1234".to_owned()),
+        ts: Set(MESSAGE_TS.to_owned()),
+        started_at: Set(unix_now()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+
+    let response =
+        test::post_slack_event(&host, &reaction_added_event("TPIPELINE5", "EvPipeline5")).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    assert!(fake
+        .calls("POST", "/github/repos/uiur/bugs/issues")
+        .is_empty());
+    let job = entities::prelude::Job::find()
+        .one(&connection)
+        .await?
+        .expect("job is not retried");
+    assert_eq!(job.status, "pending");
+
+    // The other job filed it, so the retry adds to its issue
+    entities::prelude::MessageFiling::delete_many()
+        .exec(&connection)
+        .await?;
+    entities::message_issue::Entity::insert(entities::message_issue::ActiveModel {
+        team_id: Set(team_id),
+        channel: Set("C1234".to_owned()),
+        ts: Set(MESSAGE_TS.to_owned()),
+        repo: Set("uiur/bugs".to_owned()),
+        issue_number: Set(5),
+        html_url: Set("https://github.com/uiur/bugs/issues/5".to_owned()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+    let mut job = job.into_active_model();
+    job.run_at = Set(unix_now());
+    job.update(&connection).await?;
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    assert!(fake
+        .calls("POST", "/github/repos/uiur/bugs/issues")
+        .is_empty());
+    assert_eq!(
+        fake.calls("POST", "/github/repos/uiur/bugs/issues/5/comments")
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+fn issue_form_submission(slack_team_id: &str, issue_draft_id: i32) -> String {
+    let input = |value: &str| json!({ "type": "plain_text_input", "value": value });
+    json!({
+        "type": "view_submission",
+        "team": { "id": slack_team_id, "domain": "emoji" },
+        "user": { "id": "U1234", "username": "alice", "team_id": slack_team_id },
+        "view": {
+            "id": "V1234",
+            "type": "modal",
+            "callback_id": "issue_form",
+            "private_metadata": issue_draft_id.to_string(),
+            "state": {
+                "values": {
+                    "title": { "title": input("build fails") },
+                    "body": { "body": input("on main") },
+                    "repo": { "repo": input("uiur/bugs") },
+                    "labels": { "labels": input("") },
+                    "assignees": { "assignees": input("") }
+                }
+            }
+        }
+    })
+    .to_string()
+}
+
+#[actix_rt::test]
+async fn test_issue_form_submitted_twice() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE6", 106).await?;
+
+    fake.stub(
+        "POST",
+        "/github/app/installations/106/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues",
+        201,
+        json!({ "number": 6, "html_url": "https://github.com/uiur/bugs/issues/6" }),
+    );
+
+    let issue_draft_id =
+        entities::issue_draft::Entity::insert(entities::issue_draft::ActiveModel {
+            team_id: Set(team_id),
+            user: Set("U1234".to_owned()),
+            channel: Set("C1234".to_owned()),
+            ts: Set(MESSAGE_TS.to_owned()),
+            thread_ts: Set(MESSAGE_TS.to_owned()),
+            reply_mode: Set("thread".to_owned()),
+            repo: Set("uiur/bugs".to_owned()),
+            title: Set("the build is broken".to_owned()),
+            body: Set("".to_owned()),
+            labels: Set("".to_owned()),
+            assignees: Set("".to_owned()),
+            response_url: Set(Some(format!("{}/slack/response", fake.url()))),
+            ..Default::default()
+        })
+        .exec(&connection)
+        .await?
+        .last_insert_id;
+
+    // The modal can be opened and submitted again before the first submission is filed
+    let payload = issue_form_submission("TPIPELINE6", issue_draft_id);
+    for _ in 0..2 {
+        let response = test::post_slack_form(
+            &host,
+            "/webhook/slack/interactions",
+            &[("payload", &payload)],
+        )
+        .await?;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 2);
+
+    let issues = fake.calls("POST", "/github/repos/uiur/bugs/issues");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].body["title"], "build fails");
+
+    // The prompt is replaced, so it can't file the message again
+    let replaced = fake.calls("POST", "/slack/response");
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(replaced[0].body["replace_original"], true);
+    assert!(replaced[0].body["text"]
+        .as_str()
+        .unwrap()
+        .contains("https://github.com/uiur/bugs/issues/6"));
+
+    assert!(entities::prelude::IssueDraft::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+    assert!(entities::prelude::Job::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_issue_closed_retried_replies_once() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE7", 107).await?;
+    entities::message_issue::Entity::insert(entities::message_issue::ActiveModel {
+        team_id: Set(team_id),
+        channel: Set("C1234".to_owned()),
+        ts: Set(MESSAGE_TS.to_owned()),
+        repo: Set("uiur/bugs".to_owned()),
+        issue_number: Set(7),
+        html_url: Set("https://github.com/uiur/bugs/issues/7".to_owned()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+
+    stub_slack_message(&fake, "TPIPELINE7");
+    fake.stub(
+        "POST",
+        "/slack/reactions.add",
+        200,
+        json!({ "ok": false, "error": "internal_error" }),
+    );
+
+    let event = json!({
+        "action": "closed",
+        "issue": {
+            "number": 7,
+            "title": "build fails",
+            "html_url": "https://github.com/uiur/bugs/issues/7"
+        },
+        "repository": { "full_name": "uiur/bugs" },
+        "sender": { "login": "uiur", "type": "User" }
+    });
+    let response = test::post_github_event(&host, "issues", &event).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+    assert!(fake.calls("POST", "/slack/chat.postMessage").is_empty());
+
+    fake.stub("POST", "/slack/reactions.add", 200, json!({ "ok": true }));
+    let job = entities::prelude::Job::find()
+        .one(&connection)
+        .await?
+        .expect("job is not retried");
+    let mut job = job.into_active_model();
+    job.run_at = Set(unix_now());
+    job.update(&connection).await?;
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    assert_eq!(fake.calls("POST", "/slack/reactions.add").len(), 2);
+    assert_eq!(fake.calls("POST", "/slack/chat.postMessage").len(), 1);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_thread_linked_twice_comments_once() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    create_team(&connection, "TPIPELINE8", 108).await?;
+
+    stub_slack_message(&fake, "TPIPELINE8");
+    fake.stub(
+        "POST",
+        "/github/app/installations/108/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    fake.stub(
+        "GET",
+        "/github/repos/uiur/bugs/issues/7",
+        200,
+        json!({
+            "number": 7,
+            "title": "build fails",
+            "html_url": "https://github.com/uiur/bugs/issues/7"
+        }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues/7/comments",
+        201,
+        json!({ "html_url": "https://github.com/uiur/bugs/issues/1#issuecomment-1" }),
+    );
+
+    for event_id in ["EvPipeline8a", "EvPipeline8b"] {
+        let event = json!({
+            "type": "event_callback",
+            "event_id": event_id,
+            "team_id": "TPIPELINE8",
+            "event_time": unix_now(),
+            "event": {
+                "type": "app_mention",
+                "user": "U1234",
+                "text": "<@UBOT> link <https://github.com/uiur/bugs/issues/7>",
+                "channel": "C1234",
+                "ts": "1660000000.000200",
+                "thread_ts": MESSAGE_TS
+            }
+        });
+        let response = test::post_slack_event(&host, &event).await?;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 2);
+
+    let comments = fake.calls("POST", "/github/repos/uiur/bugs/issues/7/comments");
+    assert_eq!(comments.len(), 1);
+    assert!(comments[0].body["body"]
+        .as_str()
+        .unwrap()
+        .starts_with("Linked from Slack"));
+    assert_eq!(fake.calls("POST", "/slack/chat.postMessage").len(), 2);
+
+    Ok(())
+}
+
+fn issue_card_action(
+    slack_team_id: &str,
+    message_issue_id: i32,
+    action_id: &str,
+    value: Option<&str>,
+) -> String {
+    let mut action = json!({
+        "action_id": action_id,
+        "block_id": format!("issue_card:{}", message_issue_id)
+    });
+    if let Some(value) = value {
+        action["selected_option"] = json!({ "value": value });
+    }
+    json!({
+        "type": "block_actions",
+        "team": { "id": slack_team_id },
+        "user": { "id": "U1234" },
+        "channel": { "id": "C1234" },
+        "container": { "message_ts": "1660000000.000200" },
+        "actions": [action]
+    })
+    .to_string()
+}
+
+async fn create_message_issue(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    number: i32,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let message_issue_id =
+        entities::message_issue::Entity::insert(entities::message_issue::ActiveModel {
+            team_id: Set(team_id),
+            channel: Set("C1234".to_owned()),
+            ts: Set(MESSAGE_TS.to_owned()),
+            repo: Set("uiur/bugs".to_owned()),
+            issue_number: Set(number),
+            html_url: Set(format!("https://github.com/uiur/bugs/issues/{}", number)),
+            ..Default::default()
+        })
+        .exec(connection)
+        .await?
+        .last_insert_id;
+
+    Ok(message_issue_id)
+}
+
+#[actix_rt::test]
+async fn test_issue_move_retried_files_one_copy() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE9", 109).await?;
+    entities::reaction::Entity::insert(entities::reaction::ActiveModel {
+        team_id: Set(team_id),
+        name: Set("memo".to_owned()),
+        repo: Set("uiur/tasks".to_owned()),
+        ..Default::default()
+    })
+    .exec(&connection)
+    .await?;
+    let message_issue_id = create_message_issue(&connection, team_id, 9).await?;
+
+    fake.stub(
+        "POST",
+        "/github/app/installations/109/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+    let issue = json!({
+        "number": 9,
+        "title": "build fails",
+        "html_url": "https://github.com/uiur/bugs/issues/9"
+    });
+    fake.stub(
+        "GET",
+        "/github/repos/uiur/bugs/issues/9",
+        200,
+        issue.clone(),
+    );
+    fake.stub("PATCH", "/github/repos/uiur/bugs/issues/9", 200, issue);
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/tasks/issues",
+        201,
+        json!({ "number": 1, "title": "build fails", "html_url": "https://github.com/uiur/tasks/issues/1" }),
+    );
+    fake.stub(
+        "GET",
+        "/github/repos/uiur/tasks/issues/1",
+        200,
+        json!({ "number": 1, "title": "build fails", "html_url": "https://github.com/uiur/tasks/issues/1" }),
+    );
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues/9/comments",
+        500,
+        json!({ "message": "Server Error" }),
+    );
+
+    let payload = issue_card_action(
+        "TPIPELINE9",
+        message_issue_id,
+        "change_repo",
+        Some("uiur/tasks"),
+    );
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    fake.stub(
+        "POST",
+        "/github/repos/uiur/bugs/issues/9/comments",
+        201,
+        json!({ "html_url": "https://github.com/uiur/bugs/issues/1#issuecomment-1" }),
+    );
+    let job = entities::prelude::Job::find()
+        .one(&connection)
+        .await?
+        .expect("job is not retried");
+    let mut job = job.into_active_model();
+    job.run_at = Set(unix_now());
+    job.update(&connection).await?;
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    assert_eq!(
+        fake.calls("POST", "/github/repos/uiur/tasks/issues").len(),
+        1
+    );
+    let comments = fake.calls("POST", "/github/repos/uiur/bugs/issues/9/comments");
+    assert_eq!(comments.len(), 2);
+    assert_eq!(
+        comments[1].body["body"],
+        "Moved to https://github.com/uiur/tasks/issues/1"
+    );
+
+    let message_issue = entities::prelude::MessageIssue::find_by_id(message_issue_id)
+        .one(&connection)
+        .await?
+        .expect("message issue is deleted");
+    assert_eq!(message_issue.repo, "uiur/tasks");
+    assert_eq!(message_issue.issue_number, 1);
+    assert_eq!(message_issue.moved_from_repo, None);
+    assert!(entities::prelude::Job::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_assign_to_me_without_linked_account() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE10", 110).await?;
+    let message_issue_id = create_message_issue(&connection, team_id, 10).await?;
+
+    fake.stub(
+        "POST",
+        "/github/app/installations/110/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+
+    let payload = issue_card_action("TPIPELINE10", message_issue_id, "assign_to_me", None);
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    // Slack names aren't taken as GitHub logins
+    assert!(fake
+        .calls("POST", "/github/repos/uiur/bugs/issues/10/assignees")
+        .is_empty());
+    let ephemerals = fake.calls("POST", "/slack/chat.postEphemeral");
+    assert_eq!(ephemerals.len(), 1);
+    assert_eq!(ephemerals[0].body["user"], "U1234");
+    assert!(ephemerals[0].body["text"]
+        .as_str()
+        .unwrap()
+        .contains("Link your GitHub account"));
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_issue_move_to_repo_outside_rules() -> TestResult {
+    let fake = FakeServer::start();
+    let services = fake.services();
+    let (host, connection) = test::spawn_app_with(services.clone()).await;
+    let team_id = create_team(&connection, "TPIPELINE11", 111).await?;
+    let message_issue_id = create_message_issue(&connection, team_id, 11).await?;
+
+    fake.stub(
+        "POST",
+        "/github/app/installations/111/access_tokens",
+        201,
+        json!({ "token": "ghs_test", "expires_at": "2099-01-01T00:00:00Z" }),
+    );
+
+    let payload = issue_card_action(
+        "TPIPELINE11",
+        message_issue_id,
+        "change_repo",
+        Some("someone/else"),
+    );
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(emoji_to_do::work_off(&connection, &services).await?, 1);
+
+    assert!(fake
+        .calls("POST", "/github/repos/someone/else/issues")
+        .is_empty());
+    let ephemerals = fake.calls("POST", "/slack/chat.postEphemeral");
+    assert_eq!(ephemerals.len(), 1);
+    assert_eq!(ephemerals[0].body["user"], "U1234");
+    // The job is done, not left to be retried
+    assert!(entities::prelude::Job::find()
+        .all(&connection)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
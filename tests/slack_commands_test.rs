@@ -0,0 +1,104 @@
+use emoji_to_do::entities;
+
+use sea_orm::{DatabaseConnection, EntityTrait, ModelTrait, Set};
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+async fn create_team(connection: &DatabaseConnection) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set("TEAM".to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    Ok(team_id)
+}
+
+async fn run_command(
+    host: &str,
+    team_id: &str,
+    text: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let response = test::post_slack_form(
+        host,
+        "/webhook/slack/commands",
+        &[
+            ("team_id", team_id),
+            ("user_id", "U1234"),
+            ("command", "/emoji-to-do"),
+            ("text", text),
+        ],
+    )
+    .await?;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let json: serde_json::Value = response.json().await?;
+    assert_eq!(json["response_type"], "ephemeral");
+    Ok(json)
+}
+
+#[actix_rt::test]
+async fn test_slack_commands_add_list_and_remove() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+    create_team(&connection).await?;
+
+    let json = run_command(&host, "TEAM", "add :bug: uiur/bugs @uiur").await?;
+    assert_eq!(json["text"], ":bug: now files issues to uiur/bugs");
+
+    let reaction = entities::prelude::Reaction::find_by_slack_team_id_and_name("TEAM", "bug")
+        .one(&connection)
+        .await?
+        .expect("reaction is not created");
+    assert_eq!(reaction.repo, "uiur/bugs");
+    assert!(reaction.include_thread);
+    let reaction_assignees = reaction
+        .find_related(entities::prelude::ReactionAssignee)
+        .all(&connection)
+        .await?;
+    assert_eq!(reaction_assignees.len(), 1);
+    assert_eq!(reaction_assignees[0].name, "uiur");
+
+    let json = run_command(&host, "TEAM", "add :bug: uiur/sandbox").await?;
+    assert_eq!(json["text"], ":bug: already has a rule");
+
+    let json = run_command(&host, "TEAM", "list").await?;
+    assert_eq!(json["text"], ":bug: → uiur/bugs (@uiur)");
+    assert_eq!(json["blocks"][0]["text"]["text"], json["text"]);
+
+    let json = run_command(&host, "TEAM", "remove :bug:").await?;
+    assert_eq!(json["text"], "removed the rule of :bug:");
+    let reactions = entities::prelude::Reaction::find().all(&connection).await?;
+    assert!(reactions.is_empty());
+
+    let json = run_command(&host, "TEAM", "remove :bug:").await?;
+    assert_eq!(json["text"], ":bug: has no rule");
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_commands_help() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+    create_team(&connection).await?;
+
+    let json = run_command(&host, "TEAM", "").await?;
+    assert!(json["text"].as_str().unwrap().starts_with("*Usage*"));
+
+    let json = run_command(&host, "TEAM", "add :bug:").await?;
+    let text = json["text"].as_str().unwrap();
+    assert!(text.starts_with("unknown command: `add :bug:`"));
+    assert!(text.contains("*Usage*"));
+
+    let json = run_command(&host, "OTHER", "list").await?;
+    assert_eq!(
+        json["text"],
+        "emoji-to-do is not installed to this workspace"
+    );
+
+    Ok(())
+}
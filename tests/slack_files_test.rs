@@ -0,0 +1,119 @@
+use emoji_to_do::{entities, secret};
+use hmac::{Hmac, Mac};
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+
+mod fake_server;
+mod test;
+
+use fake_server::FakeServer;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[actix_rt::test]
+async fn test_get_slack_file_with_invalid_signature() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    for query in ["?signature=deadbeef", "?signature=not-hex", ""] {
+        let response = client
+            .get(format!("{}/files/1/F1234{}", host, query))
+            .send()
+            .await
+            .expect("failed to fetch api");
+        assert!(
+            response.status().is_client_error(),
+            "{}: {}",
+            query,
+            response.status()
+        );
+    }
+
+    Ok(())
+}
+
+async fn create_team(connection: &DatabaseConnection) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set("TFILES".to_owned()),
+        slack_bot_token: Set(Some(secret::encrypt("xoxb-test")?)),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    Ok(team_id)
+}
+
+// Signed with a key derived from MASTER_KEY, not MASTER_KEY itself
+fn signature(team_id: i32, file_id: &str) -> String {
+    let master_key = std::env::var("MASTER_KEY").expect("MASTER_KEY is expected");
+    let mut key = Hmac::<sha2::Sha256>::new_from_slice(master_key.as_bytes()).unwrap();
+    key.update(b"emoji-to-do file proxy");
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&key.finalize().into_bytes()).unwrap();
+    mac.update(format!("{}/{}", team_id, file_id).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn stub_file_info(fake: &FakeServer, mimetype: &str, size: u64) {
+    fake.stub(
+        "GET",
+        "/slack/files.info",
+        200,
+        json!({
+            "ok": true,
+            "file": {
+                "id": "F1234",
+                "name": "screenshot",
+                "mimetype": mimetype,
+                "filetype": "png",
+                "url_private": format!("{}/slack/files-pri/F1234/screenshot", fake.url()),
+                "permalink": "https://example.slack.com/files/U1/F1234/screenshot",
+                "size": size
+            }
+        }),
+    );
+}
+
+#[actix_rt::test]
+async fn test_get_slack_file() -> TestResult {
+    let fake = FakeServer::start();
+    let (host, connection) = test::spawn_app_with(fake.services()).await;
+    let team_id = create_team(&connection).await?;
+    let url = format!(
+        "{}/files/{}/F1234?signature={}",
+        host,
+        team_id,
+        signature(team_id, "F1234")
+    );
+
+    stub_file_info(&fake, "image/png", 1024);
+    let response = reqwest::get(&url).await?;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(
+        response.headers()["content-security-policy"],
+        "default-src 'none'; sandbox"
+    );
+    let downloads = fake.calls("GET", "/slack/files-pri/F1234/screenshot");
+    assert_eq!(downloads.len(), 1);
+    assert_eq!(
+        downloads[0].authorization.as_deref(),
+        Some("Bearer xoxb-test")
+    );
+
+    // SVG can run scripts, and big files aren't held in memory
+    for (mimetype, size) in [("image/svg+xml", 1024), ("image/png", 100 * 1024 * 1024)] {
+        stub_file_info(&fake, mimetype, size);
+        let response = reqwest::get(&url).await?;
+        assert_eq!(response.status().as_u16(), 404, "{}", mimetype);
+    }
+    assert_eq!(
+        fake.calls("GET", "/slack/files-pri/F1234/screenshot").len(),
+        1
+    );
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use emoji_to_do::entities;
+
+use sea_orm::EntityTrait;
+use serde_json::json;
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+fn block_actions(block_id: &str, action: serde_json::Value) -> String {
+    let mut action = action;
+    action["block_id"] = json!(block_id);
+
+    json!({
+        "type": "block_actions",
+        "team": { "id": "TEAM", "domain": "emoji" },
+        "user": { "id": "U1234", "username": "uiur", "team_id": "TEAM" },
+        "channel": { "id": "C1234", "name": "general" },
+        "container": {
+            "type": "message",
+            "message_ts": "1660000001.000200",
+            "channel_id": "C1234",
+            "is_ephemeral": false
+        },
+        "response_url": "https://hooks.slack.com/actions/T/1/abc",
+        "actions": [action]
+    })
+    .to_string()
+}
+
+fn view_submission(values: serde_json::Value) -> String {
+    json!({
+        "type": "view_submission",
+        "team": { "id": "TEAM", "domain": "emoji" },
+        "user": { "id": "U1234", "username": "uiur", "team_id": "TEAM" },
+        "view": {
+            "id": "V1234",
+            "type": "modal",
+            "callback_id": "issue_form",
+            "private_metadata": "5",
+            "state": { "values": values }
+        }
+    })
+    .to_string()
+}
+
+fn issue_form_values(repo: &str) -> serde_json::Value {
+    json!({
+        "title": { "title": { "type": "plain_text_input", "value": "build fails" } },
+        "body": { "body": { "type": "plain_text_input", "value": "on main" } },
+        "repo": { "repo": { "type": "plain_text_input", "value": repo } },
+        "labels": { "labels": { "type": "plain_text_input", "value": "bug, good first issue" } },
+        "assignees": { "assignees": { "type": "plain_text_input", "value": null } }
+    })
+}
+
+#[actix_rt::test]
+async fn test_slack_interactions_issue_card_action_enqueues_job() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let payload = block_actions(
+        "issue_card:3",
+        json!({
+            "type": "static_select",
+            "action_id": "change_repo",
+            "selected_option": { "value": "uiur/bugs" }
+        }),
+    );
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await
+    .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].slack_team_id, "TEAM");
+
+    let payload: serde_json::Value = serde_json::from_str(&jobs[0].payload)?;
+    assert_eq!(payload["type"], "issue_card_action");
+    assert_eq!(payload["message_issue_id"], 3);
+    assert_eq!(payload["action_id"], "change_repo");
+    assert_eq!(payload["value"], "uiur/bugs");
+    assert_eq!(payload["user"], "U1234");
+    assert_eq!(payload["channel"], "C1234");
+    assert_eq!(payload["message_ts"], "1660000001.000200");
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_interactions_ignores_other_blocks() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let payload = block_actions(
+        "something_else",
+        json!({ "type": "button", "action_id": "close_issue" }),
+    );
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await
+    .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert!(jobs.is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_interactions_issue_form_submission_enqueues_job() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let payload = view_submission(issue_form_values("uiur/bugs"));
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await
+    .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.text().await?, "");
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs.len(), 1);
+
+    let payload: serde_json::Value = serde_json::from_str(&jobs[0].payload)?;
+    assert_eq!(payload["type"], "issue_form_submitted");
+    assert_eq!(payload["issue_draft_id"], 5);
+    assert_eq!(payload["title"], "build fails");
+    assert_eq!(payload["body"], "on main");
+    assert_eq!(payload["repo"], "uiur/bugs");
+    assert_eq!(payload["labels"], json!(["bug", "good first issue"]));
+    assert_eq!(payload["assignees"], json!([]));
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_interactions_issue_form_with_invalid_repo() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let payload = view_submission(issue_form_values("bugs"));
+    let response = test::post_slack_form(
+        &host,
+        "/webhook/slack/interactions",
+        &[("payload", &payload)],
+    )
+    .await
+    .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let json: serde_json::Value = response.json().await?;
+    assert_eq!(json["response_action"], "errors");
+    assert!(json["errors"]["repo"].is_string());
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert!(jobs.is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_interactions_with_invalid_signature() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/webhook/slack/interactions", host))
+        .form(&[(
+            "payload",
+            block_actions("issue_card:3", json!({ "action_id": "close_issue" })),
+        )])
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 401);
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
-use std::net::TcpListener;
+use std::{
+    net::TcpListener,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use emoji_to_do::entities;
+use emoji_to_do::{entities, Services};
+use hmac::{Hmac, Mac};
 use sea_orm::{DatabaseConnection, EntityTrait, Set, SqlxSqliteConnector};
 use sqlx::sqlite::SqlitePoolOptions;
 
@@ -23,12 +27,23 @@ async fn setup_db() -> DatabaseConnection {
 
 pub async fn spawn_app() -> (String, DatabaseConnection) {
     dotenv::from_filename(".env.test").ok();
+    spawn(Services::from_env()).await
+}
+
+/// Spawns the app calling Slack and GitHub through `services`, e.g. those of a fake server.
+pub async fn spawn_app_with(services: Services) -> (String, DatabaseConnection) {
+    dotenv::from_filename(".env.test").ok();
+    spawn(services).await
+}
+
+async fn spawn(services: Services) -> (String, DatabaseConnection) {
     let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
     let port = listener.local_addr().unwrap().port();
 
     let connection = setup_db().await;
 
-    let server = emoji_to_do::run(listener, connection.clone()).expect("Failed to bind address");
+    let server =
+        emoji_to_do::run(listener, connection.clone(), services).expect("Failed to bind address");
     let _ = actix_rt::spawn(server);
 
     (format!("http://127.0.0.1:{}", port), connection)
@@ -54,7 +69,7 @@ pub async fn create_user(
     let active_model = emoji_to_do::entities::user::ActiveModel {
         slack_team_id: Set("TEAM".to_owned()),
         slack_user_id: Set("User".to_owned()),
-        slack_token: Set("TOKEN".to_owned()),
+        slack_token: Set(Some("TOKEN".to_owned())),
         ..Default::default()
     };
 
@@ -71,3 +86,79 @@ pub async fn create_user(
 
     Ok(user)
 }
+
+pub fn sign_slack_request(timestamp: &str, body: &str) -> String {
+    let signing_secret =
+        std::env::var("SLACK_SIGNING_SECRET").expect("SLACK_SIGNING_SECRET is expected");
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(signing_secret.as_bytes()).unwrap();
+    mac.update(format!("v0:{}:{}", timestamp, body).as_bytes());
+    format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub async fn post_slack_event(
+    host: &str,
+    event: &serde_json::Value,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let body = event.to_string();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+
+    reqwest::Client::new()
+        .post(format!("{}/webhook/slack/events", host))
+        .header("Content-Type", "application/json")
+        .header("X-Slack-Request-Timestamp", &timestamp)
+        .header("X-Slack-Signature", sign_slack_request(&timestamp, &body))
+        .body(body)
+        .send()
+        .await
+}
+
+pub fn sign_github_request(body: &str) -> String {
+    let webhook_secret =
+        std::env::var("GITHUB_WEBHOOK_SECRET").expect("GITHUB_WEBHOOK_SECRET is expected");
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(webhook_secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub async fn post_github_event(
+    host: &str,
+    event: &str,
+    payload: &serde_json::Value,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let body = payload.to_string();
+
+    reqwest::Client::new()
+        .post(format!("{}/webhook/github", host))
+        .header("Content-Type", "application/json")
+        .header("X-GitHub-Event", event)
+        .header("X-Hub-Signature-256", sign_github_request(&body))
+        .body(body)
+        .send()
+        .await
+}
+
+pub async fn post_slack_form(
+    host: &str,
+    path: &str,
+    params: &[(&str, &str)],
+) -> Result<reqwest::Response, reqwest::Error> {
+    let body = serde_urlencoded::to_string(params).unwrap();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+
+    reqwest::Client::new()
+        .post(format!("{}{}", host, path))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Slack-Request-Timestamp", &timestamp)
+        .header("X-Slack-Signature", sign_slack_request(&timestamp, &body))
+        .body(body)
+        .send()
+        .await
+}
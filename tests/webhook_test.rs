@@ -0,0 +1,296 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use emoji_to_do::entities;
+
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+
+mod test;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+async fn create_team(
+    connection: &DatabaseConnection,
+    slack_team_id: &str,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let team_id = entities::team::Entity::insert(entities::team::ActiveModel {
+        name: Set("TEAM EMOJI".to_owned()),
+        slack_team_id: Set(slack_team_id.to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    Ok(team_id)
+}
+
+async fn create_reaction(
+    connection: &DatabaseConnection,
+    team_id: i32,
+    name: &str,
+    repo: &str,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let reaction_id = entities::reaction::Entity::insert(entities::reaction::ActiveModel {
+        team_id: Set(team_id),
+        name: Set(name.to_owned()),
+        repo: Set(repo.to_owned()),
+        ..Default::default()
+    })
+    .exec(connection)
+    .await?
+    .last_insert_id;
+
+    Ok(reaction_id)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn reaction_added_event(event_id: &str, reaction: &str) -> serde_json::Value {
+    json!({
+        "type": "event_callback",
+        "event_id": event_id,
+        "team_id": "TEAM",
+        "event_time": unix_now(),
+        "event": {
+            "type": "reaction_added",
+            "user": "U1234",
+            "reaction": reaction,
+            "item": {
+                "type": "message",
+                "channel": "C1234",
+                "ts": "1660000000.000100"
+            }
+        }
+    })
+}
+
+async fn find_slack_event(
+    connection: &DatabaseConnection,
+    event_id: &str,
+) -> Result<Option<entities::slack_event::Model>, Box<dyn std::error::Error>> {
+    let slack_event = entities::prelude::SlackEvent::find()
+        .filter(entities::slack_event::Column::EventId.eq(event_id))
+        .one(connection)
+        .await?;
+
+    Ok(slack_event)
+}
+
+#[actix_rt::test]
+async fn test_find_reaction_by_slack_team_id_and_name() -> TestResult {
+    let (_host, connection) = test::spawn_app().await;
+
+    let team_id = create_team(&connection, "TEAM").await?;
+    let other_team_id = create_team(&connection, "OTHER").await?;
+
+    create_reaction(&connection, team_id, "eyes", "uiur/sandbox").await?;
+    create_reaction(&connection, team_id, "bug", "uiur/bugs").await?;
+    create_reaction(&connection, other_team_id, "eyes", "other/sandbox").await?;
+    create_reaction(&connection, other_team_id, "memo", "other/memo").await?;
+
+    let cases = [
+        ("TEAM", "eyes", Some("uiur/sandbox")),
+        ("TEAM", "bug", Some("uiur/bugs")),
+        ("TEAM", "memo", None),
+        ("OTHER", "eyes", Some("other/sandbox")),
+        ("OTHER", "bug", None),
+        ("UNKNOWN", "eyes", None),
+    ];
+
+    for (slack_team_id, name, expected_repo) in cases {
+        let reaction =
+            entities::prelude::Reaction::find_by_slack_team_id_and_name(slack_team_id, name)
+                .one(&connection)
+                .await?;
+
+        assert_eq!(
+            reaction.as_ref().map(|r| r.repo.as_str()),
+            expected_repo,
+            "{} :{}:",
+            slack_team_id,
+            name
+        );
+    }
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_reaction_added_without_matching_rule() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let team_id = create_team(&connection, "TEAM").await?;
+    create_reaction(&connection, team_id, "eyes", "uiur/sandbox").await?;
+    create_reaction(&connection, team_id, "bug", "uiur/bugs").await?;
+
+    let response = test::post_slack_event(&host, &reaction_added_event("Ev0001", "thumbsup"))
+        .await
+        .expect("failed to fetch api");
+
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert!(jobs.is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_reaction_added_enqueues_job() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let team_id = create_team(&connection, "TEAM").await?;
+    create_reaction(&connection, team_id, "eyes", "uiur/sandbox").await?;
+    let reaction_id = create_reaction(&connection, team_id, "bug", "uiur/bugs").await?;
+
+    let response = test::post_slack_event(&host, &reaction_added_event("Ev0001", "bug"))
+        .await
+        .expect("failed to fetch api");
+
+    assert_eq!(response.status().as_u16(), 200);
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].slack_team_id, "TEAM");
+    assert_eq!(jobs[0].status, "pending");
+
+    let payload: serde_json::Value = serde_json::from_str(&jobs[0].payload)?;
+    assert_eq!(payload["type"], "reaction_added");
+    assert_eq!(payload["reaction_id"], reaction_id);
+    assert_eq!(payload["item"]["channel"], "C1234");
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_records_processed_event() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let response = test::post_slack_event(&host, &reaction_added_event("Ev0001", "thumbsup"))
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let slack_event = find_slack_event(&connection, "Ev0001")
+        .await?
+        .expect("slack event is not recorded");
+    assert_eq!(slack_event.slack_team_id, "TEAM");
+    assert!(slack_event.finished_at.is_some());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_skips_duplicated_event() -> TestResult {
+    let (host, connection) = test::spawn_app().await;
+
+    let team_id = create_team(&connection, "TEAM").await?;
+    create_reaction(&connection, team_id, "eyes", "uiur/sandbox").await?;
+
+    // Already processed, and still being processed by the first delivery
+    for (event_id, finished_at) in [("Ev0001", Some(unix_now())), ("Ev0002", None)] {
+        entities::slack_event::Entity::insert(entities::slack_event::ActiveModel {
+            event_id: Set(event_id.to_owned()),
+            slack_team_id: Set("TEAM".to_owned()),
+            event_time: Set(unix_now()),
+            started_at: Set(unix_now()),
+            finished_at: Set(finished_at),
+            ..Default::default()
+        })
+        .exec(&connection)
+        .await?;
+
+        let response = test::post_slack_event(&host, &reaction_added_event(event_id, "eyes"))
+            .await
+            .expect("failed to fetch api");
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let jobs = entities::prelude::Job::find().all(&connection).await?;
+    assert!(jobs.is_empty());
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_url_verification() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+
+    let response = test::post_slack_event(
+        &host,
+        &json!({
+            "type": "url_verification",
+            "challenge": "3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P"
+        }),
+    )
+    .await
+    .expect("failed to fetch api");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.text().await?,
+        "3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P"
+    );
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_with_invalid_signature() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+    let client = reqwest::Client::new();
+
+    let body = json!({
+        "type": "url_verification",
+        "challenge": "challenge"
+    })
+    .to_string();
+
+    let response = client
+        .post(format!("{}/webhook/slack/events", host))
+        .header("Content-Type", "application/json")
+        .body(body.clone())
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let stale_timestamp = "1531420618";
+    let response = client
+        .post(format!("{}/webhook/slack/events", host))
+        .header("Content-Type", "application/json")
+        .header("X-Slack-Request-Timestamp", stale_timestamp)
+        .header(
+            "X-Slack-Signature",
+            test::sign_slack_request(stale_timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await
+        .expect("failed to fetch api");
+    assert_eq!(response.status().as_u16(), 401);
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_slack_events_with_too_large_body() -> TestResult {
+    let (host, _connection) = test::spawn_app().await;
+
+    // Refused before the signature is checked, so an unsigned request is enough
+    let response = reqwest::Client::new()
+        .post(format!("{}/webhook/slack/events", host))
+        .header("Content-Type", "application/json")
+        .body(vec![b' '; 2 * 1024 * 1024])
+        .send()
+        .await?;
+    assert_eq!(response.status().as_u16(), 413);
+
+    Ok(())
+}